@@ -0,0 +1,329 @@
+//! Macro that writes MIX programs inside Rust code in the syntax of MIXAL.
+//!
+//! The macro expands inside the `mix-rs` crate and calls the helpers of its `dsl` module.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Memory image of the program, written as MIXAL statements that end with `;`.
+///
+/// A statement is `LABEL: OP ADDRESS,I(F);` where the label and every part of the operand
+/// may be omitted, and `ORIG`, `EQU`, `CON` and `END` work as in MIXAL. An address is
+/// a number, a label, `*` or an expression in parentheses such as `(BUF+1)` or `(*+2)`.
+/// Labels become constants of the block that the macro expands to, so they may be used
+/// before their definition and their values are known at compile time. The image itself
+/// is built when the block runs, by inserting the word of every statement.
+///
+/// Mistakes are reported at the offending token: an unknown mnemonic is not a variant of
+/// `Operation`, and a misplaced token, an index outside 1–6 or a malformed field are
+/// errors of the macro. `tests/errors.rs` checks where they are reported.
+///
+/// ```ignore
+/// let image = mix! {
+///            ORIG 3000;
+///     START: ENT1 10;
+///     LOOP:  DEC1 1;
+///            J1P  LOOP;
+///            HLT;
+///            END  START;
+/// };
+/// ```
+#[proc_macro]
+pub fn mix(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(code) => code,
+        Err(Error(span, message)) => compile_error(span, &message),
+    }
+}
+
+/// Message about the token at the span.
+struct Error(Span, String);
+
+fn error<T>(span: Span, message: &str) -> Result<T, Error> {
+    Err(Error(span, message.to_string()))
+}
+
+/// Location counter, the expression of the last `ORIG` and the words after it.
+struct Counter {
+    origin: TokenStream,
+    offset: i64,
+}
+
+impl Counter {
+    fn location(&self) -> TokenStream {
+        let mut location = self.origin.clone();
+        location.extend(code("+"));
+        location.extend(tokens(Literal::i64_unsuffixed(self.offset)));
+        parenthesized(location)
+    }
+}
+
+fn expand(input: TokenStream) -> Result<TokenStream, Error> {
+    // Mixed site hygiene keeps the image apart from the labels of the program.
+    let image = Ident::new("image", Span::mixed_site());
+    let mut counter = Counter {
+        origin: code("0"),
+        offset: 0,
+    };
+    let mut body = code("let mut");
+    body.extend(tokens(image.clone()));
+    body.extend(code("= crate::Image::default();"));
+    for (statement, end) in statements(input)? {
+        body.extend(self::statement(&statement, end, &mut counter, &image)?);
+    }
+    body.extend(tokens(image));
+    Ok(tokens(Group::new(Delimiter::Brace, body)))
+}
+
+/// Tokens of each statement with the span of the `;` that ends it.
+fn statements(input: TokenStream) -> Result<Vec<(Vec<TokenTree>, Span)>, Error> {
+    let mut statements = vec![];
+    let mut statement = vec![];
+    for token in input {
+        match token {
+            TokenTree::Punct(ref punct) if punct.as_char() == ';' => {
+                statements.push((std::mem::take(&mut statement), punct.span()));
+            }
+            token => statement.push(token),
+        }
+    }
+    match statement.last() {
+        Some(last) => error(last.span(), "expected `;` after the statement"),
+        None => Ok(statements),
+    }
+}
+
+fn statement(
+    tokens: &[TokenTree],
+    end: Span,
+    counter: &mut Counter,
+    image: &Ident,
+) -> Result<TokenStream, Error> {
+    let (label, rest) = match tokens {
+        [TokenTree::Ident(label), TokenTree::Punct(colon), rest @ ..] if colon.as_char() == ':' => {
+            (Some(label), rest)
+        }
+        _ => (None, tokens),
+    };
+    let (operation, operand) = match rest {
+        [TokenTree::Ident(operation), operand @ ..] => (operation, operand),
+        [token, ..] => return error(token.span(), "expected an operation"),
+        [] => return error(end, "expected an operation"),
+    };
+    let mut code = TokenStream::new();
+    match (operation.to_string().as_str(), label) {
+        ("EQU", Some(label)) => {
+            return Ok(constant(label, value(operation, operand, counter)?));
+        }
+        ("EQU", None) => return error(operation.span(), "EQU needs a label"),
+        (_, Some(label)) => code.extend(constant(label, counter.location())),
+        (_, None) => {}
+    }
+    match operation.to_string().as_str() {
+        "ORIG" => {
+            counter.origin = value(operation, operand, counter)?;
+            counter.offset = 0;
+        }
+        "END" => {
+            code.extend(self::tokens(image.clone()));
+            code.extend(self::code(".start = crate::dsl::location"));
+            code.extend(parenthesized(value(operation, operand, counter)?));
+            code.extend(self::code(";"));
+        }
+        "CON" => {
+            let mut word = self::code("crate::dsl::word");
+            word.extend(parenthesized(value(operation, operand, counter)?));
+            code.extend(insert(image, counter, word));
+            counter.offset += 1;
+        }
+        _ => {
+            let mut arguments = self::code("crate::Operation::");
+            arguments.extend(self::tokens(operation.clone()));
+            arguments.extend(self::code(","));
+            arguments.extend(instruction(operand, counter)?);
+            let mut word = self::code("crate::dsl::instruction");
+            word.extend(parenthesized(arguments));
+            code.extend(insert(image, counter, word));
+            counter.offset += 1;
+        }
+    }
+    Ok(code)
+}
+
+/// Address, index and field arguments of the instruction.
+fn instruction(operand: &[TokenTree], counter: &Counter) -> Result<TokenStream, Error> {
+    let (minus, operand) = match operand {
+        [TokenTree::Punct(minus), rest @ ..] if minus.as_char() == '-' => (Some(minus), rest),
+        _ => (None, operand),
+    };
+    let (address, rest) = match (minus, operand) {
+        (None, []) => (code("0"), operand),
+        (Some(minus), []) => return error(minus.span(), "expected an address after `-`"),
+        (None, [TokenTree::Group(group)]) if is_field(group) => (code("0"), operand),
+        (minus, [address, rest @ ..]) => (signed(minus.is_some(), address, counter), rest),
+    };
+    let (index, field) = match rest {
+        [] => (None, None),
+        [TokenTree::Group(field)] => (None, Some(field)),
+        [TokenTree::Punct(comma), index] if comma.as_char() == ',' => (Some(index), None),
+        [TokenTree::Punct(comma), index, TokenTree::Group(field)] if comma.as_char() == ',' => {
+            (Some(index), Some(field))
+        }
+        [TokenTree::Punct(comma), ..] if comma.as_char() == ',' => {
+            return error(comma.span(), "expected an index register after `,`")
+        }
+        [token, ..] => return error(token.span(), "unexpected token"),
+    };
+
+    let mut arguments = address;
+    arguments.extend(code(","));
+    match index {
+        None => arguments.extend(code("None")),
+        Some(TokenTree::Literal(index))
+            if matches!(
+                index.to_string().as_str(),
+                "1" | "2" | "3" | "4" | "5" | "6"
+            ) =>
+        {
+            arguments.extend(code(&format!("Some(crate::IndexNumber::I{})", index)))
+        }
+        Some(index) => return error(index.span(), "index register must be 1–6"),
+    }
+    arguments.extend(code(","));
+    match field {
+        None => arguments.extend(code("None")),
+        Some(field) => arguments.extend(self::field(field)?),
+    }
+    Ok(arguments)
+}
+
+/// Whether the group is `(L:R)`, the field of an instruction without an address.
+fn is_field(group: &Group) -> bool {
+    group.delimiter() == Delimiter::Parenthesis
+        && matches!(
+            group.stream().into_iter().collect::<Vec<_>>().as_slice(),
+            [TokenTree::Literal(_), TokenTree::Punct(colon), TokenTree::Literal(_)]
+                if colon.as_char() == ':'
+        )
+}
+
+/// Modification of the field in parentheses, `(L:R)` or `(F)`.
+fn field(group: &Group) -> Result<TokenStream, Error> {
+    let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+    let arguments = match (group.delimiter(), inner.as_slice()) {
+        (
+            Delimiter::Parenthesis,
+            [TokenTree::Literal(l), TokenTree::Punct(colon), TokenTree::Literal(r)],
+        ) if colon.as_char() == ':' => {
+            let mut bounds = tokens(l.clone());
+            bounds.extend(code(","));
+            bounds.extend(tokens(r.clone()));
+            let mut field = code("crate::Modification::field");
+            field.extend(parenthesized(bounds));
+            field
+        }
+        (Delimiter::Parenthesis, [TokenTree::Literal(value)]) => {
+            let mut byte = code("crate::Byte::new");
+            byte.extend(parenthesized(tokens(value.clone())));
+            let mut field = code("crate::Modification::from");
+            field.extend(parenthesized(byte));
+            field
+        }
+        _ => return error(group.span(), "expected a field such as (1:5) or (16)"),
+    };
+    let mut field = code("Some");
+    field.extend(parenthesized(arguments));
+    Ok(field)
+}
+
+/// Value of the single operand of a pseudo-operation, possibly negated.
+fn value(
+    operation: &Ident,
+    operand: &[TokenTree],
+    counter: &Counter,
+) -> Result<TokenStream, Error> {
+    match operand {
+        [] => error(operation.span(), &format!("{} needs an address", operation)),
+        [TokenTree::Punct(minus), value] if minus.as_char() == '-' => {
+            Ok(signed(true, value, counter))
+        }
+        [value] => Ok(signed(false, value, counter)),
+        [TokenTree::Punct(minus), _, token, ..] if minus.as_char() == '-' => {
+            error(token.span(), "unexpected token")
+        }
+        [_, token, ..] => error(token.span(), "unexpected token"),
+    }
+}
+
+/// Expression of an address: a number, a label, `*`, or an expression in parentheses
+/// where `*` may start the expression.
+fn signed(minus: bool, address: &TokenTree, counter: &Counter) -> TokenStream {
+    let value = match address {
+        TokenTree::Punct(star) if star.as_char() == '*' => counter.location(),
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+            let mut inner = group.stream().into_iter();
+            match inner.next() {
+                Some(TokenTree::Punct(star)) if star.as_char() == '*' => {
+                    let mut expression = counter.location();
+                    expression.extend(inner);
+                    parenthesized(expression)
+                }
+                // Parentheses of the macro do not trip the lint on the arguments of calls.
+                _ => parenthesized(group.stream()),
+            }
+        }
+        address => tokens(address.clone()),
+    };
+    if minus {
+        let mut negated = code("-");
+        negated.extend(value);
+        parenthesized(negated)
+    } else {
+        value
+    }
+}
+
+/// `const LABEL: i64 = VALUE;` that may stay unused.
+fn constant(label: &Ident, value: TokenStream) -> TokenStream {
+    let mut constant = code("#[allow(dead_code)] const");
+    constant.extend(tokens(label.clone()));
+    constant.extend(code(": i64 ="));
+    constant.extend(value);
+    constant.extend(code(";"));
+    constant
+}
+
+/// Statement that puts the word at the location counter.
+fn insert(image: &Ident, counter: &Counter, word: TokenStream) -> TokenStream {
+    let mut arguments = code("crate::dsl::location");
+    arguments.extend(parenthesized(counter.location()));
+    arguments.extend(code(","));
+    arguments.extend(word);
+    let mut insert = tokens(image.clone());
+    insert.extend(code(".words.insert"));
+    insert.extend(parenthesized(arguments));
+    insert.extend(code(";"));
+    insert
+}
+
+fn compile_error(span: Span, message: &str) -> TokenStream {
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    let mut arguments = Group::new(Delimiter::Parenthesis, tokens(Literal::string(message)));
+    arguments.set_span(span);
+    let mut error = tokens(Ident::new("compile_error", span));
+    error.extend(tokens(bang));
+    error.extend(tokens(arguments));
+    error
+}
+
+fn code(text: &str) -> TokenStream {
+    text.parse().expect("valid Rust")
+}
+
+fn tokens(token: impl Into<TokenTree>) -> TokenStream {
+    TokenStream::from(token.into())
+}
+
+fn parenthesized(inner: TokenStream) -> TokenStream {
+    tokens(Group::new(Delimiter::Parenthesis, inner))
+}
@@ -0,0 +1,141 @@
+//! Mistakes in programs of `mix!` fail to compile with errors at the offending tokens.
+//!
+//! The macro only expands inside `mix-rs`, so every program is checked by cargo in a crate
+//! of its own that stands in for the items of `mix-rs` that the expansion refers to.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Items of `mix-rs` that the expansion of an instruction refers to.
+const PRELUDE: &str = "use mix_dsl::mix;
+
+mod dsl {
+    use super::{IndexNumber, Modification, Operation};
+
+    pub fn location(value: i64) -> usize {
+        value as usize
+    }
+
+    pub fn instruction(
+        _: Operation,
+        address: i64,
+        _: Option<IndexNumber>,
+        _: Option<Modification>,
+    ) -> i64 {
+        address
+    }
+}
+
+#[derive(Default)]
+struct Image {
+    words: std::collections::BTreeMap<usize, i64>,
+}
+
+enum Operation {
+    LDA,
+}
+
+enum IndexNumber {
+    I1,
+}
+
+struct Modification;
+";
+
+/// Errors of `cargo check` in short form for the program inside `main` of the crate.
+fn check(name: &str, program: &str) -> String {
+    let target = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let root = target.join("errors").join(name);
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.0.0\"\nedition = \"2018\"\n\n\
+             [dependencies]\nmix-dsl = {{ path = {:?} }}\n\n[workspace]\n",
+            name,
+            env!("CARGO_MANIFEST_DIR")
+        ),
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/main.rs"),
+        format!(
+            "{}\nfn main() {{\n    let _ = mix! {{\n{}\n    }};\n}}\n",
+            PRELUDE, program
+        ),
+    )
+    .unwrap();
+    let output = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .args(["check", "--quiet", "--offline", "--message-format", "short"])
+        .current_dir(&root)
+        .env("CARGO_TARGET_DIR", target.join("errors-target"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{} compiles", name);
+    String::from_utf8(output.stderr).unwrap()
+}
+
+/// Line of `src/main.rs` with the first line of the program.
+fn program_line() -> usize {
+    PRELUDE.lines().count() + 4
+}
+
+/// Asserts the error at the line of the program and the column of the offending token.
+fn assert_error(name: &str, program: &str, (line, column): (usize, usize), message: &str) {
+    let errors = check(name, program);
+    let expected = format!(
+        "src/main.rs:{}:{}: error",
+        program_line() + line - 1,
+        column
+    );
+    assert!(
+        errors
+            .lines()
+            .any(|error| error.starts_with(&expected) && error.contains(message)),
+        "expected {} {}, got:\n{}",
+        expected,
+        message,
+        errors
+    );
+}
+
+#[test]
+fn reports_missing_operations() {
+    assert_error(
+        "missing_operation",
+        "        HLT;\nSTART:  3000;",
+        (2, 9),
+        "expected an operation",
+    );
+}
+
+#[test]
+fn reports_index_registers_outside_1_to_6() {
+    assert_error(
+        "index_register",
+        "        LDA  1000,7;",
+        (1, 19),
+        "index register must be 1–6",
+    );
+}
+
+#[test]
+fn reports_malformed_fields() {
+    assert_error(
+        "malformed_field",
+        "        LDA  1000(1:);",
+        (1, 18),
+        "expected a field such as (1:5) or (16)",
+    );
+}
+
+#[test]
+fn reports_unknown_mnemonics() {
+    assert_error(
+        "unknown_mnemonic",
+        "        LDB  1000;",
+        (1, 9),
+        "no variant or associated item named `LDB` found for enum `Operation`",
+    );
+}
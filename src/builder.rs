@@ -0,0 +1,302 @@
+//! Builder that emits instructions and words from Rust code, for generators of MIX programs.
+
+use crate::{Address, Image, Instruction, Word, MEMORY_SIZE};
+use std::collections::BTreeMap;
+
+/// Location that is known by name or only by its handle, possibly before it is bound.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Label(usize);
+
+/// Memory image with the locations of the named labels.
+#[derive(Debug, PartialEq)]
+pub struct Program {
+    pub image: Image,
+    pub symbols: BTreeMap<String, usize>,
+}
+
+/// Emits words at a location counter, like a MIXAL program without the text.
+///
+/// Instructions may refer to labels that are bound later, their addresses are patched
+/// when the program is built.
+#[derive(Debug, Default)]
+pub struct ProgramBuilder {
+    location: usize,
+    words: BTreeMap<usize, Word>,
+    /// Name, for named labels, and location of every label by its handle.
+    labels: Vec<(Option<String>, Option<usize>)>,
+    /// Instructions that refer to labels, their addresses are offsets from the labels.
+    references: Vec<(usize, Instruction, Label)>,
+    start: Option<Label>,
+    errors: Vec<String>,
+}
+
+impl ProgramBuilder {
+    /// Builder with the location counter at `origin`.
+    pub fn new(origin: usize) -> Self {
+        ProgramBuilder {
+            location: origin,
+            ..ProgramBuilder::default()
+        }
+    }
+
+    /// Current value of the location counter.
+    pub fn location(&self) -> usize {
+        self.location
+    }
+
+    /// Moves the location counter, like ORIG.
+    pub fn orig(&mut self, location: usize) -> &mut Self {
+        self.location = location;
+        self
+    }
+
+    /// Label with the name, the same one every time the name is given.
+    pub fn label(&mut self, name: &str) -> Label {
+        let position = self
+            .labels
+            .iter()
+            .position(|(n, _)| n.as_deref() == Some(name));
+        match position {
+            Some(i) => Label(i),
+            None => {
+                self.labels.push((Some(name.to_string()), None));
+                Label(self.labels.len() - 1)
+            }
+        }
+    }
+
+    /// Label without a name, it is known only by the handle.
+    pub fn anonymous(&mut self) -> Label {
+        self.labels.push((None, None));
+        Label(self.labels.len() - 1)
+    }
+
+    /// Anonymous label bound to the current location, for jumps back.
+    pub fn here(&mut self) -> Label {
+        let label = self.anonymous();
+        self.bind(label);
+        label
+    }
+
+    /// Binds the label to the current location, a label may be bound only once.
+    pub fn bind(&mut self, label: Label) -> &mut Self {
+        match self.labels[label.0].1 {
+            Some(_) => {
+                let error = format!("{} is bound twice", self.describe(label));
+                self.errors.push(error);
+            }
+            None => self.labels[label.0].1 = Some(self.location),
+        }
+        self
+    }
+
+    /// Emits the instruction at the location counter.
+    pub fn instruction(&mut self, instruction: Instruction) -> &mut Self {
+        self.word(Word::from(instruction))
+    }
+
+    /// Emits the instruction with the location of the label added to its address,
+    /// so `Address::new(1)` refers to the word after the label.
+    pub fn refer(&mut self, instruction: Instruction, label: Label) -> &mut Self {
+        self.references.push((self.location, instruction, label));
+        self.instruction(instruction)
+    }
+
+    /// Emits the word at the location counter, like CON.
+    pub fn word(&mut self, word: Word) -> &mut Self {
+        if self.location >= MEMORY_SIZE {
+            let error = format!("location {} is outside memory", self.location);
+            self.errors.push(error);
+        } else {
+            self.words.insert(self.location, word);
+        }
+        self.location += 1;
+        self
+    }
+
+    /// Sets the location where the program starts, like END.
+    pub fn start(&mut self, label: Label) -> &mut Self {
+        self.start = Some(label);
+        self
+    }
+
+    /// Patches the references to labels and collects the locations of named labels.
+    pub fn build(mut self) -> Result<Program, Vec<String>> {
+        let references = std::mem::take(&mut self.references);
+        for (location, instruction, label) in references {
+            let target = match self.resolve(label) {
+                Some(target) => target as i64 + instruction.address.value() as i64,
+                None => continue,
+            };
+            if target.abs() >= MEMORY_SIZE as i64 {
+                let error = format!("address {} at {} does not fit", target, location);
+                self.errors.push(error);
+                continue;
+            }
+            let patched = Instruction::new(
+                instruction.operation,
+                Address::new(target as i16),
+                instruction.index,
+                instruction.modification,
+            );
+            if let Some(word) = self.words.get_mut(&location) {
+                *word = Word::from(patched);
+            }
+        }
+        let start = match self.start {
+            Some(label) => self.resolve(label).unwrap_or_default(),
+            None => 0,
+        };
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+        let symbols = self
+            .labels
+            .into_iter()
+            .filter_map(|(name, location)| Some((name?, location?)))
+            .collect();
+        Ok(Program {
+            image: Image {
+                words: self.words,
+                start,
+            },
+            symbols,
+        })
+    }
+
+    /// Location of the label, an error when it is never bound.
+    fn resolve(&mut self, label: Label) -> Option<usize> {
+        let location = self.labels[label.0].1;
+        if location.is_none() {
+            let error = format!("{} is never bound", self.describe(label));
+            if !self.errors.contains(&error) {
+                self.errors.push(error);
+            }
+        }
+        location
+    }
+
+    fn describe(&self, label: Label) -> String {
+        match &self.labels[label.0].0 {
+            Some(name) => format!("label {}", name),
+            None => format!("anonymous label #{}", label.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::mixal::assemble;
+    use crate::{IndexNumber, Mix, Modification, Operation::*, Sign};
+
+    fn instruction(operation: crate::Operation, address: i16) -> Instruction {
+        Instruction::new(operation, Address::new(address), None, None)
+    }
+
+    #[test]
+    fn patches_forward_references() {
+        let mut builder = ProgramBuilder::new(3000);
+        let start = builder.label("START");
+        let table = builder.label("TABLE");
+        let done = builder.anonymous();
+        builder.bind(start).instruction(instruction(ENT1, 3));
+        let again = builder.here();
+        builder
+            .refer(
+                Instruction::new(
+                    ADD,
+                    Address::new(-1),
+                    Some(IndexNumber::I1),
+                    Some(Modification::field(0, 5)),
+                ),
+                table,
+            )
+            .instruction(instruction(DEC1, 1))
+            .refer(instruction(J1P, 0), again)
+            .refer(instruction(JMP, 0), done)
+            .bind(table)
+            .word(Word::from_magnitude(Sign::Plus, 10))
+            .word(Word::from_magnitude(Sign::Plus, 20))
+            .word(Word::from_magnitude(Sign::Minus, 5))
+            .bind(done)
+            .instruction(instruction(HLT, 0))
+            .start(start);
+
+        let program = builder.build().unwrap();
+
+        let assembled = assemble(
+            "           ORIG 3000
+START      ENT1 3
+1H         ADD  TABLE-1,1(0:5)
+           DEC1 1
+           J1P  1B
+           JMP  2F
+TABLE      CON  10
+           CON  20
+           CON  -5
+2H         HLT
+           END  START",
+        )
+        .unwrap();
+        assert_eq!(program.image, assembled.image);
+        assert_eq!(
+            program.symbols.into_iter().collect::<Vec<_>>(),
+            vec![("START".to_string(), 3000), ("TABLE".to_string(), 3005)]
+        );
+
+        let mut mix = Mix::default();
+        mix.load_image(&program.image);
+        let mix = mix.run();
+        assert!(mix.halted);
+        assert_eq!(mix.a.value(), 25);
+    }
+
+    #[test]
+    fn moves_the_location_counter_like_orig() {
+        let mut builder = ProgramBuilder::new(100);
+        let data = builder.label("DATA");
+        builder.refer(instruction(LDA, 0), data).orig(200);
+        assert_eq!(builder.location(), 200);
+        builder.bind(data).word(Word::from_magnitude(Sign::Plus, 7));
+        assert_eq!(builder.location(), 201);
+
+        let program = builder.build().unwrap();
+
+        let assembled = assemble(
+            "           ORIG 100
+           LDA  DATA
+           ORIG 200
+DATA       CON  7
+           END  100",
+        )
+        .unwrap();
+        assert_eq!(program.image.words, assembled.image.words);
+        assert_eq!(program.symbols["DATA"], 200);
+    }
+
+    #[test]
+    fn reports_misused_labels() {
+        let mut builder = ProgramBuilder::new(3998);
+        let twice = builder.label("TWICE");
+        let never = builder.anonymous();
+        let far = builder.label("FAR");
+        builder
+            .bind(twice)
+            .bind(twice)
+            .refer(instruction(JMP, 0), never)
+            .refer(instruction(JMP, 0), never)
+            .refer(instruction(JMP, 100), far)
+            .bind(far);
+
+        assert_eq!(
+            builder.build(),
+            Err(vec![
+                "label TWICE is bound twice".to_string(),
+                "location 4000 is outside memory".to_string(),
+                "anonymous label #1 is never bound".to_string(),
+                "address 4101 at 4000 does not fit".to_string(),
+            ])
+        );
+    }
+}
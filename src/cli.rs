@@ -0,0 +1,1152 @@
+//! Command line interface.
+
+use crate::debugger::{self, fault_message, Debugger};
+use crate::device::{
+    self, CardPunch, CardReader, Disk, LinePrinter, Lines, Paging, PaperTape, Tape, Typewriter,
+    Words,
+};
+use crate::lsp;
+use crate::mixal::cross_reference::cross_reference;
+use crate::mixal::deck::deck;
+use crate::mixal::disassembler::disassemble_memory;
+use crate::mixal::linker::link;
+use crate::mixal::listing::{dump_line, listing, macro_listing};
+use crate::mixal::macros::{assemble_with_macros, Expansion};
+use crate::mixal::mdk::{assemble_mdk, import_mdk};
+use crate::mixal::object::{assemble_object, is_object, Object};
+use crate::mixal::preprocessor::{preprocess, Preprocessed};
+use crate::mixal::{assemble, formatter, Error, Program};
+use crate::{Fault, Image, Mix, Word, MEMORY_SIZE};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+/// Exit code when the command did what it was asked to, or the program halted.
+pub const SUCCESS: i32 = 0;
+/// Exit code when the input has problems, like source that is not formatted.
+pub const FAILURE: i32 = 1;
+/// Exit code when the command line is wrong or a file cannot be read or written.
+pub const USAGE: i32 = 2;
+/// Exit code when the program stops on a word that is not an instruction.
+pub const INVALID_INSTRUCTION: i32 = 3;
+/// Exit code when the program refers to a location outside of memory.
+pub const INVALID_ADDRESS: i32 = 4;
+/// Exit code when the program uses a unit without a device.
+pub const NO_DEVICE: i32 = 5;
+/// Exit code when a device fails, like a card reader at the end of its cards.
+pub const DEVICE_ERROR: i32 = 6;
+/// Exit code when the program runs more instructions than `--limit` allows.
+pub const LIMIT: i32 = 7;
+
+/// Lines on a page of the line printer unless `--page-length` is given.
+const PAGE_LENGTH: usize = 60;
+
+/// Values of the symbols of a program by their names.
+type Symbols = BTreeMap<String, i64>;
+
+const HELP: &str = "\
+usage: mix-rs asm [--macros | --mdk] [--object | --listing | --xref | --deck NAME]
+                  [-o OUTPUT] FILE
+       mix-rs link [--origin LOCATION] [-o OUTPUT] OBJECT...
+       mix-rs run [--macros | --mdk] [--limit COUNT] [DEVICES] PROGRAM
+       mix-rs run --go --cards DECK [--limit COUNT] [DEVICES]
+       mix-rs debug [--macros | --mdk] [DEVICES] PROGRAM
+       mix-rs debug --go --cards DECK [DEVICES]
+       mix-rs disasm [--macros | --mdk] PROGRAM
+       mix-rs dump [--macros | --mdk] PROGRAM
+       mix-rs fmt [--check] [FILE...]
+       mix-rs lsp
+    asm      assembles MIXAL into an image, which run, disasm and dump read, or into
+             --object   a relocatable module for link
+             --listing  the words next to the lines of the source
+             --xref     the symbols with the lines that define and use them
+             --deck     punched cards for the loading routine, named NAME
+    --macros expands macros before assembling
+    --mdk    reads the dialect of GNU MDK
+    link     places modules one after another from LOCATION, 0 by default,
+             and resolves their symbols into an image
+    run      runs the program until HLT or until it ran COUNT instructions,
+             --go presses the GO button, which loads the DECK from the cards
+    debug    runs the program step by step under commands from standard input,
+             help lists them, the typewriter is not available
+    DEVICES  --cards FILE, --punch FILE, --printer FILE, --paper-tape FILE and
+             --typewriter, which reads standard input; the printer and the
+             typewriter write to standard output unless --printer is given,
+             tapes 0-7 and disks 8-15 are scratch storage that starts empty;
+             --page-length N starts a new page of the printer every N lines,
+             60 by default, with a form feed (--form-feed), the default for
+             --printer, or a line of dashes (--separator), the default otherwise
+    PROGRAM  MIXAL source, an image written by asm or link, or a program
+             compiled by GNU MDK
+    disasm   writes the words of the program back as MIXAL
+    dump     lists every word with its fields, value, characters and instruction
+    fmt      lays out MIXAL in canonical columns, files are rewritten in place
+             and standard input is written to standard output
+    --check  only reports files that are not formatted and exits with 1
+    lsp      serves the language server protocol on standard input and output
+exit codes: 0 success or HLT, 1 errors in the input, 2 wrong usage or unreadable files,
+            3 invalid instruction, 4 invalid address, 5 no device on the unit,
+            6 device failure, 7 limit reached";
+
+/// Runs the command of the arguments, which follow the name of the program,
+/// and returns the exit code. The typewriter of `run` keeps `input` to read it as
+/// the program asks for lines.
+pub fn run(
+    args: &[String],
+    mut input: Box<dyn BufRead>,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> i32 {
+    let result = match args.split_first() {
+        Some((command, args)) => match command.as_str() {
+            "asm" => asm(args, out, err),
+            "link" => link_modules(args, out, err),
+            "run" => run_program(args, input, out, err),
+            "debug" => debug(args, &mut input, out, err),
+            "disasm" => disasm(args, out, err),
+            "dump" => dump(args, out, err),
+            "fmt" => fmt(args, &mut input, out, err),
+            "lsp" if args.is_empty() => Ok(lsp::serve(&mut input, out)),
+            _ => Err(HELP.to_string()),
+        },
+        None => Err(HELP.to_string()),
+    };
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            let _ = writeln!(err, "{}", message);
+            USAGE
+        }
+    }
+}
+
+/// Options of a command and the arguments that are not options.
+struct Arguments<'a> {
+    options: BTreeMap<&'a str, &'a str>,
+    files: Vec<&'a str>,
+}
+
+impl<'a> Arguments<'a> {
+    /// Splits the arguments, `valued` options take the argument after them as their value.
+    fn parse(args: &'a [String], flags: &[&str], valued: &[&str]) -> Result<Self, String> {
+        let mut arguments = Arguments {
+            options: BTreeMap::new(),
+            files: vec![],
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let arg = arg.as_str();
+            if flags.contains(&arg) {
+                arguments.options.insert(arg, "");
+            } else if valued.contains(&arg) {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("option {} needs a value\n{}", arg, HELP))?;
+                arguments.options.insert(arg, value);
+            } else if arg.starts_with('-') && arg.len() > 1 {
+                return Err(format!("unknown option {}\n{}", arg, HELP));
+            } else {
+                arguments.files.push(arg);
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn flag(&self, option: &str) -> bool {
+        self.options.contains_key(option)
+    }
+
+    fn value(&self, option: &str) -> Option<&'a str> {
+        self.options.get(option).copied()
+    }
+
+    /// At most one of the options.
+    fn exclusive(&self, options: &[&str]) -> Result<(), String> {
+        let given: Vec<&str> = options
+            .iter()
+            .copied()
+            .filter(|option| self.flag(option))
+            .collect();
+        match given[..] {
+            [first, second, ..] => Err(format!("{} and {} exclude each other", first, second)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The only argument that is not an option.
+    fn file(&self) -> Result<&'a str, String> {
+        match self.files[..] {
+            [file] => Ok(file),
+            _ => Err(HELP.to_string()),
+        }
+    }
+}
+
+fn fmt(
+    args: &[String],
+    input: &mut dyn Read,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> Result<i32, String> {
+    let arguments = Arguments::parse(args, &["--check"], &[])?;
+    let check = arguments.flag("--check");
+
+    if arguments.files.is_empty() {
+        let mut source = String::new();
+        input
+            .read_to_string(&mut source)
+            .map_err(|e| format!("cannot read standard input: {}", e))?;
+        if check {
+            return Ok(if formatter::is_formatted(&source) {
+                SUCCESS
+            } else {
+                report(err, "standard input")
+            });
+        }
+        write!(out, "{}", formatter::format(&source)).map_err(|e| e.to_string())?;
+        return Ok(SUCCESS);
+    }
+
+    let mut code = SUCCESS;
+    for file in arguments.files {
+        let source = read(file)?;
+        let formatted = formatter::format(&source);
+        if formatted == source {
+            continue;
+        }
+        if check {
+            code = report(err, file);
+        } else {
+            fs::write(file, formatted).map_err(|e| format!("cannot write {}: {}", file, e))?;
+        }
+    }
+    Ok(code)
+}
+
+fn report(err: &mut dyn Write, name: &str) -> i32 {
+    let _ = writeln!(err, "{} is not formatted", name);
+    FAILURE
+}
+
+fn asm(args: &[String], out: &mut dyn Write, err: &mut dyn Write) -> Result<i32, String> {
+    let arguments = Arguments::parse(
+        args,
+        &["--macros", "--mdk", "--object", "--listing", "--xref"],
+        &["--deck", "-o"],
+    )?;
+    arguments.exclusive(&["--macros", "--mdk"])?;
+    arguments.exclusive(&["--object", "--listing", "--xref", "--deck"])?;
+    let file = arguments.file()?;
+    let text = read(file)?;
+    let preprocessed = match preprocessed(file, text) {
+        Ok(preprocessed) => preprocessed,
+        Err(errors) => return Ok(failure(err, errors)),
+    };
+
+    let output = if arguments.flag("--xref") {
+        cross_reference(&preprocessed.source, &preprocessed.line_names())
+    } else if arguments.flag("--object") {
+        match assemble_object(&preprocessed.source) {
+            Ok(object) => object.to_string(),
+            Err(errors) => return Ok(failure(err, preprocessed.original(errors))),
+        }
+    } else {
+        let (expansion, program) = match assembled(&preprocessed, &arguments) {
+            Ok(assembled) => assembled,
+            Err(errors) => return Ok(failure(err, errors)),
+        };
+        match (arguments.value("--deck"), expansion) {
+            (Some(name), _) => match deck(name, &program.image) {
+                Ok(cards) => cards,
+                Err(message) => return Ok(failure(err, vec![message])),
+            },
+            (None, Some(expansion)) if arguments.flag("--listing") => {
+                macro_listing(&expansion, &program, &preprocessed.line_names())
+            }
+            (None, None) if arguments.flag("--listing") => {
+                listing(&preprocessed.source, &program, &preprocessed.line_names())
+            }
+            _ => image_text(&program.image),
+        }
+    };
+    write_output(arguments.value("-o"), &output, out)?;
+    Ok(SUCCESS)
+}
+
+fn link_modules(args: &[String], out: &mut dyn Write, err: &mut dyn Write) -> Result<i32, String> {
+    let arguments = Arguments::parse(args, &[], &["--origin", "-o"])?;
+    if arguments.files.is_empty() {
+        return Err(HELP.to_string());
+    }
+    let origin = match arguments.value("--origin") {
+        Some(origin) => origin
+            .parse::<usize>()
+            .map_err(|_| format!("invalid location {}", origin))?,
+        None => 0,
+    };
+    let mut objects = vec![];
+    for file in &arguments.files {
+        match read(file)?.parse::<Object>() {
+            Ok(object) => objects.push(object),
+            Err(message) => return Ok(failure(err, vec![format!("{}: {}", file, message)])),
+        }
+    }
+    let modules: Vec<(&str, &Object)> = arguments.files.iter().copied().zip(&objects).collect();
+    match link(&modules, origin) {
+        Ok(image) => {
+            write_output(arguments.value("-o"), &image_text(&image), out)?;
+            Ok(SUCCESS)
+        }
+        Err(errors) => Ok(failure(err, errors)),
+    }
+}
+
+fn run_program(
+    args: &[String],
+    input: Box<dyn BufRead>,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> Result<i32, String> {
+    let arguments = Arguments::parse(
+        args,
+        &[
+            "--macros",
+            "--mdk",
+            "--go",
+            "--typewriter",
+            "--form-feed",
+            "--separator",
+        ],
+        &[
+            "--limit",
+            "--cards",
+            "--punch",
+            "--printer",
+            "--paper-tape",
+            "--page-length",
+        ],
+    )?;
+    let limit = match arguments.value("--limit") {
+        Some(limit) => Some(
+            limit
+                .parse::<u64>()
+                .map_err(|_| format!("invalid count {}", limit))?,
+        ),
+        None => None,
+    };
+    let typed = if arguments.flag("--typewriter") {
+        Some(input)
+    } else {
+        None
+    };
+    let printed = Lines::default();
+    let mut mix = match machine(&arguments, typed, &printed, err)? {
+        Some((mix, _)) => mix,
+        None => return Ok(FAILURE),
+    };
+    let mut count = 0;
+    while !mix.halted && mix.fault.is_none() && limit.is_none_or(|limit| count < limit) {
+        mix = mix.step();
+        count += 1;
+        // Prompts appear before the typewriter waits for the answer.
+        let text = printed.take();
+        if !text.is_empty() {
+            out.write_all(&text)
+                .and_then(|_| out.flush())
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    let code = match &mix.fault {
+        Some(Fault::InvalidInstruction(_)) => INVALID_INSTRUCTION,
+        Some(Fault::InvalidAddress(_)) => INVALID_ADDRESS,
+        Some(Fault::NoDevice(_)) => NO_DEVICE,
+        Some(Fault::Device(_, _)) => DEVICE_ERROR,
+        None if mix.halted => return Ok(SUCCESS),
+        None => LIMIT,
+    };
+    let message =
+        fault_message(&mix).unwrap_or_else(|| format!("stopped after {} instructions", count));
+    let _ = writeln!(err, "{:04}: {}", mix.program_counter, message);
+    Ok(code)
+}
+
+fn debug(
+    args: &[String],
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> Result<i32, String> {
+    let arguments = Arguments::parse(
+        args,
+        &["--macros", "--mdk", "--go", "--form-feed", "--separator"],
+        &[
+            "--cards",
+            "--punch",
+            "--printer",
+            "--paper-tape",
+            "--page-length",
+        ],
+    )?;
+    let printed = Lines::default();
+    let mut debugger = match machine(&arguments, None, &printed, err)? {
+        Some((mix, symbols)) => Debugger::new(mix, symbols),
+        None => return Ok(FAILURE),
+    };
+    debugger::debug(&mut debugger, input, out, &printed).map_err(|e| e.to_string())?;
+    Ok(SUCCESS)
+}
+
+/// Machine with the devices of the options and the program loaded, and the symbols
+/// of the program. The typewriter reads `typed`, when it is given. Errors in the program
+/// are reported and give no machine.
+fn machine(
+    arguments: &Arguments,
+    typed: Option<Box<dyn BufRead>>,
+    printed: &Lines,
+    err: &mut dyn Write,
+) -> Result<Option<(Mix, Symbols)>, String> {
+    arguments.exclusive(&["--macros", "--mdk"])?;
+    arguments.exclusive(&["--form-feed", "--separator"])?;
+    let go = arguments.flag("--go");
+    if go != arguments.files.is_empty() {
+        return Err(HELP.to_string());
+    }
+
+    let mut mix = Mix::default();
+    if let Some(file) = arguments.value("--cards") {
+        mix.attach(
+            device::CARD_READER,
+            CardReader::new(BufReader::new(open(file)?)),
+        );
+    }
+    if let Some(file) = arguments.value("--punch") {
+        mix.attach(device::CARD_PUNCH, CardPunch::new(create(file)?));
+    }
+    let page_length = match arguments.value("--page-length") {
+        Some(length) => match length.parse::<usize>() {
+            Ok(length) if length > 0 => length,
+            _ => return Err(format!("invalid page length {}", length)),
+        },
+        None => PAGE_LENGTH,
+    };
+    let paging = |default| {
+        if arguments.flag("--form-feed") {
+            Paging::FormFeed
+        } else if arguments.flag("--separator") {
+            Paging::Separator
+        } else {
+            default
+        }
+    };
+    match arguments.value("--printer") {
+        Some(file) => mix.attach(
+            device::PRINTER,
+            LinePrinter::new(create(file)?, page_length, paging(Paging::FormFeed)),
+        ),
+        None => mix.attach(
+            device::PRINTER,
+            LinePrinter::new(printed.clone(), page_length, paging(Paging::Separator)),
+        ),
+    }
+    if let Some(typed) = typed {
+        mix.attach(device::TYPEWRITER, Typewriter::new(typed, printed.clone()));
+    }
+    for unit in 0..8 {
+        mix.attach(unit, Tape::new(Words::default()));
+        mix.attach(unit + 8, Disk::new(Words::default()));
+    }
+    if let Some(file) = arguments.value("--paper-tape") {
+        mix.attach(
+            device::PAPER_TAPE,
+            PaperTape::new(BufReader::new(open(file)?)),
+        );
+    }
+
+    if go {
+        return Ok(Some((mix.go(), BTreeMap::new())));
+    }
+    match load(arguments.file()?, arguments, err)? {
+        Some((image, symbols)) => {
+            mix.load_image(&image);
+            Ok(Some((mix, symbols)))
+        }
+        None => Ok(None),
+    }
+}
+
+fn disasm(args: &[String], out: &mut dyn Write, err: &mut dyn Write) -> Result<i32, String> {
+    let arguments = Arguments::parse(args, &["--macros", "--mdk"], &[])?;
+    arguments.exclusive(&["--macros", "--mdk"])?;
+    let image = match load(arguments.file()?, &arguments, err)? {
+        Some((image, _)) => image,
+        None => return Ok(FAILURE),
+    };
+    let mut memory = vec![Word::default(); MEMORY_SIZE];
+    for (&location, &word) in &image.words {
+        memory[location] = word;
+    }
+    if let (Some(&first), Some(&last)) = (image.words.keys().next(), image.words.keys().next_back())
+    {
+        write!(out, "{}", disassemble_memory(&memory, first..last + 1))
+            .map_err(|e| e.to_string())?;
+    }
+    writeln!(out, "{:<10} END  {}", "", image.start).map_err(|e| e.to_string())?;
+    Ok(SUCCESS)
+}
+
+fn dump(args: &[String], out: &mut dyn Write, err: &mut dyn Write) -> Result<i32, String> {
+    let arguments = Arguments::parse(args, &["--macros", "--mdk"], &[])?;
+    arguments.exclusive(&["--macros", "--mdk"])?;
+    let image = match load(arguments.file()?, &arguments, err)? {
+        Some((image, _)) => image,
+        None => return Ok(FAILURE),
+    };
+    for (&location, &word) in &image.words {
+        writeln!(out, "{}", dump_line(location, word)).map_err(|e| e.to_string())?;
+    }
+    Ok(SUCCESS)
+}
+
+/// Image of the program in the file, which is MIXAL source, an image written by `asm`
+/// or a program compiled by MDK, with the symbols of the source. Errors in the source
+/// are reported and give no image.
+fn load(
+    file: &str,
+    arguments: &Arguments,
+    err: &mut dyn Write,
+) -> Result<Option<(Image, Symbols)>, String> {
+    let bytes = fs::read(file).map_err(|e| format!("cannot read {}: {}", file, e))?;
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(binary) => {
+            return match import_mdk(binary.as_bytes()) {
+                Ok(image) => Ok(Some((image, BTreeMap::new()))),
+                Err(message) => Ok(report_failure(err, file, message)),
+            }
+        }
+    };
+    // MIXAL that happens to begin like a record is still assembled.
+    let object = if is_object(&text) {
+        text.parse::<Object>().ok()
+    } else {
+        None
+    };
+    if let Some(object) = object {
+        if let Some(symbol) = object.imports.values().next() {
+            let message = format!("module imports {}, link it first", symbol);
+            return Ok(report_failure(err, file, message));
+        }
+        let image = Image {
+            words: object.words,
+            start: object.start.unwrap_or_default(),
+        };
+        let symbols = object
+            .exports
+            .into_iter()
+            .map(|(symbol, offset)| (symbol, offset as i64))
+            .collect();
+        return Ok(Some((image, symbols)));
+    }
+    let result =
+        preprocessed(file, text).and_then(|preprocessed| assembled(&preprocessed, arguments));
+    match result {
+        Ok((_, program)) => Ok(Some((program.image, program.symbols))),
+        Err(errors) => {
+            failure(err, errors);
+            Ok(None)
+        }
+    }
+}
+
+fn report_failure<T>(err: &mut dyn Write, file: &str, message: String) -> Option<T> {
+    failure(err, vec![format!("{}: {}", file, message)]);
+    None
+}
+
+/// Source of the file with its includes, `text` is the contents of the file.
+fn preprocessed(file: &str, text: String) -> Result<Preprocessed, Vec<Error>> {
+    let path = Path::new(file);
+    preprocess(path, &mut |included| {
+        if included == path {
+            Ok(text.clone())
+        } else {
+            fs::read_to_string(included)
+        }
+    })
+}
+
+/// Program of the source in the dialect of the options, with the expansion of macros.
+fn assembled(
+    preprocessed: &Preprocessed,
+    arguments: &Arguments,
+) -> Result<(Option<Expansion>, Program), Vec<Error>> {
+    let source = &preprocessed.source;
+    let result = if arguments.flag("--macros") {
+        assemble_with_macros(source).map(|(expansion, program)| (Some(expansion), program))
+    } else if arguments.flag("--mdk") {
+        assemble_mdk(source).map(|program| (None, program))
+    } else {
+        assemble(source).map(|program| (None, program))
+    };
+    result.map_err(|errors| preprocessed.original(errors))
+}
+
+/// Image in the text form of an object module that starts at location 0.
+fn image_text(image: &Image) -> String {
+    Object {
+        words: image.words.clone(),
+        start: Some(image.start),
+        ..Object::default()
+    }
+    .to_string()
+}
+
+fn failure<E: std::fmt::Display>(err: &mut dyn Write, errors: Vec<E>) -> i32 {
+    for error in errors {
+        let _ = writeln!(err, "{}", error);
+    }
+    FAILURE
+}
+
+fn write_output(file: Option<&str>, output: &str, out: &mut dyn Write) -> Result<(), String> {
+    match file {
+        Some(file) => fs::write(file, output).map_err(|e| format!("cannot write {}: {}", file, e)),
+        None => write!(out, "{}", output).map_err(|e| e.to_string()),
+    }
+}
+
+fn read(file: &str) -> Result<String, String> {
+    fs::read_to_string(file).map_err(|e| format!("cannot read {}: {}", file, e))
+}
+
+fn open(file: &str) -> Result<File, String> {
+    File::open(file).map_err(|e| format!("cannot read {}: {}", file, e))
+}
+
+fn create(file: &str) -> Result<File, String> {
+    File::create(file).map_err(|e| format!("cannot write {}: {}", file, e))
+}
+
+/// Runs the command with the standard streams.
+pub fn main(args: &[String]) -> i32 {
+    run(
+        args,
+        Box::new(io::stdin().lock()),
+        &mut io::stdout(),
+        &mut io::stderr(),
+    )
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    fn command(args: &[&str], input: &str) -> (i32, String, String) {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut out = vec![];
+        let mut err = vec![];
+        let input = Box::new(Cursor::new(input.to_string()));
+        let code = run(&args, input, &mut out, &mut err);
+        (
+            code,
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    fn temporary(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("mix-rs-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    const HELLO: &str = "\
+PRINTER    EQU  18
+           ORIG 3000
+START      OUT  TEXT(PRINTER)
+           JBUS *(PRINTER)
+           HLT
+TEXT       ALF  HELLO
+           ORIG TEXT+24
+           END  START
+";
+
+    #[test]
+    fn formats_standard_input() {
+        assert_eq!(
+            command(&["fmt"], "START LDA 1000 , 1\n"),
+            (
+                SUCCESS,
+                "START      LDA  1000,1\n".to_string(),
+                String::new()
+            )
+        );
+        assert_eq!(
+            command(&["fmt", "--check"], "START LDA 1000\n"),
+            (
+                FAILURE,
+                String::new(),
+                "standard input is not formatted\n".to_string()
+            )
+        );
+        assert_eq!(
+            command(&["fmt", "--check"], "START      LDA  1000\n"),
+            (SUCCESS, String::new(), String::new())
+        );
+    }
+
+    #[test]
+    fn checks_and_rewrites_files() {
+        let path = temporary("fmt.mixal", " HLT\n");
+        let file = path.to_str().unwrap();
+
+        assert_eq!(
+            command(&["fmt", "--check", file], ""),
+            (
+                FAILURE,
+                String::new(),
+                format!("{} is not formatted\n", file)
+            )
+        );
+        assert_eq!(
+            command(&["fmt", file], ""),
+            (SUCCESS, String::new(), String::new())
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "           HLT\n");
+        assert_eq!(
+            command(&["fmt", "--check", file], ""),
+            (SUCCESS, String::new(), String::new())
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn serves_the_language_server() {
+        let exit = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+        let input = format!("Content-Length: {}\r\n\r\n{}", exit.len(), exit);
+
+        assert_eq!(
+            command(&["lsp"], &input),
+            (FAILURE, String::new(), String::new())
+        );
+        assert_eq!(command(&["lsp", "--stdio"], "").0, USAGE);
+    }
+
+    #[test]
+    fn assembles_and_runs_programs() {
+        let source = temporary("hello.mixal", HELLO);
+        let image = std::env::temp_dir().join(format!("mix-rs-{}-hello.image", std::process::id()));
+        let (source, image) = (source.to_str().unwrap(), image.to_str().unwrap());
+
+        assert_eq!(
+            command(&["asm", source, "-o", image], ""),
+            (SUCCESS, String::new(), String::new())
+        );
+        assert_eq!(
+            fs::read_to_string(image).unwrap(),
+            "START 3000\n\
+             WORD 3000 + 3003 00 18 37\n\
+             WORD 3001 + 3001 00 18 34\n\
+             WORD 3002 + 0000 00 02 05\n\
+             WORD 3003 + 0517 13 13 16\n"
+        );
+        for program in &[source, image] {
+            assert_eq!(
+                command(&["run", program], ""),
+                (SUCCESS, "HELLO\n".to_string(), String::new())
+            );
+        }
+        let (code, listing, _) = command(&["asm", "--listing", source], "");
+        assert_eq!(code, SUCCESS);
+        assert!(
+            listing.contains("3003  + 0517 13 13 16  06  TEXT       ALF  HELLO"),
+            "{}",
+            listing
+        );
+        fs::remove_file(source).unwrap();
+        fs::remove_file(image).unwrap();
+    }
+
+    #[test]
+    fn lists_included_lines_with_their_files() {
+        let library = temporary("listed.mixal", "TEXT       ALF  HELLO\n");
+        let name = library.file_name().unwrap().to_str().unwrap();
+        let path = temporary(
+            "listing.mixal",
+            &format!(
+                "           OUT  TEXT(18)\n           INCLUDE {}\n           END  0\n",
+                name
+            ),
+        );
+        let source = path.to_str().unwrap();
+        let included = library.to_str().unwrap();
+
+        let (code, listing, _) = command(&["asm", "--listing", source], "");
+        assert_eq!(code, SUCCESS);
+        assert!(
+            listing.contains(&format!("0001  + 0517 13 13 16  {}:1   TEXT", included)),
+            "{}",
+            listing
+        );
+        let (code, xref, _) = command(&["asm", "--xref", source], "");
+        assert_eq!(code, SUCCESS);
+        assert!(xref.contains(&format!("{}:1", source)), "{}", xref);
+        fs::remove_file(path).unwrap();
+        fs::remove_file(library).unwrap();
+    }
+
+    #[test]
+    fn debugs_programs_with_commands_from_standard_input() {
+        let path = temporary("debug.mixal", HELLO);
+        let source = path.to_str().unwrap();
+
+        assert_eq!(
+            command(&["debug", source], "break START+1\ncontinue\nc\n"),
+            (
+                SUCCESS,
+                "(mix) breakpoint at 3001
+(mix) HELLO
+breakpoint at 3001
+=>*3001             JBUS 3001(18)
+(mix) halted
+(mix) \n"
+                    .to_string(),
+                String::new()
+            )
+        );
+        assert_eq!(command(&["debug", "--typewriter", source], "").0, USAGE);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn runs_with_devices_attached() {
+        let source = temporary(
+            "copy.mixal",
+            "START      IN   100(16)
+           JBUS *(16)
+           OUT  100(17)
+           OUT  100(18)
+           JMP  START
+           END  START
+",
+        );
+        let cards = temporary("cards.txt", "FIRST CARD\nSECOND CARD\n");
+        let punch = std::env::temp_dir().join(format!("mix-rs-{}-punch.txt", std::process::id()));
+        let (source, cards, punch) = (
+            source.to_str().unwrap(),
+            cards.to_str().unwrap(),
+            punch.to_str().unwrap(),
+        );
+
+        let (code, out, err) = command(&["run", "--cards", cards, "--punch", punch, source], "");
+
+        assert_eq!(code, DEVICE_ERROR);
+        assert_eq!(out, "FIRST CARD\nSECOND CARD\n");
+        assert_eq!(err, "0000: unit 16: no more input\n");
+        assert_eq!(
+            fs::read_to_string(punch).unwrap(),
+            "FIRST CARD\nSECOND CARD\n"
+        );
+        for file in &[source, cards, punch] {
+            fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn pages_the_printer_as_asked() {
+        let source = temporary(
+            "pages.mixal",
+            "START      OUT  TEXT(18)
+           OUT  TEXT(18)
+           OUT  TEXT(18)
+           HLT
+TEXT       ALF  LINE
+           ORIG TEXT+24
+           END  START
+",
+        );
+        let printer =
+            std::env::temp_dir().join(format!("mix-rs-{}-printer.txt", std::process::id()));
+        let (source, printer) = (source.to_str().unwrap(), printer.to_str().unwrap());
+
+        let (code, out, _) = command(&["run", "--page-length", "2", source], "");
+        assert_eq!(code, SUCCESS);
+        assert_eq!(out, format!("LINE\nLINE\n{}\nLINE\n", "-".repeat(120)));
+
+        let (code, out, _) = command(&["run", "--page-length", "1", "--form-feed", source], "");
+        assert_eq!(code, SUCCESS);
+        assert_eq!(out, "LINE\n\x0cLINE\n\x0cLINE\n");
+
+        let args = ["run", "--printer", printer, "--separator", source];
+        assert_eq!(command(&args, "").0, SUCCESS);
+        assert_eq!(fs::read_to_string(printer).unwrap(), "LINE\nLINE\nLINE\n");
+
+        let (code, _, err) = command(&["run", "--page-length", "0", source], "");
+        assert_eq!(code, USAGE);
+        assert_eq!(err, "invalid page length 0\n");
+        let (code, _, err) = command(&["run", "--form-feed", "--separator", source], "");
+        assert_eq!(code, USAGE);
+        assert_eq!(err, "--form-feed and --separator exclude each other\n");
+        for file in &[source, printer] {
+            fs::remove_file(file).unwrap();
+        }
+    }
+
+    /// Standard input that types every line into the transcript when the program reads it.
+    struct Keyboard {
+        lines: std::vec::IntoIter<&'static str>,
+        transcript: Lines,
+    }
+
+    impl Read for Keyboard {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.lines.next() {
+                Some(line) => {
+                    writeln!(self.transcript, "> {}", line)?;
+                    (&mut *buf).write(format!("{}\n", line).as_bytes())
+                }
+                None => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn types_and_prints_while_the_program_runs() {
+        let source = temporary(
+            "echo.mixal",
+            "TYPEWRITER EQU  19
+START      OUT  PROMPT(TYPEWRITER)
+           IN   ANSWER(TYPEWRITER)
+           OUT  ANSWER(TYPEWRITER)
+           JMP  START
+PROMPT     ALF  NAME:
+           ORIG PROMPT+14
+ANSWER     ORIG *+14
+           END  START
+",
+        );
+        let transcript = Lines::default();
+        let keyboard = Keyboard {
+            lines: vec!["ADA", "BOB"].into_iter(),
+            transcript: transcript.clone(),
+        };
+        let args: Vec<String> = ["run", "--typewriter", source.to_str().unwrap()]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+
+        let code = run(
+            &args,
+            Box::new(BufReader::new(keyboard)),
+            &mut transcript.clone(),
+            &mut vec![],
+        );
+
+        assert_eq!(code, DEVICE_ERROR);
+        assert_eq!(
+            transcript.lines(),
+            vec!["NAME:", "> ADA", "ADA", "NAME:", "> BOB", "BOB", "NAME:"]
+        );
+        fs::remove_file(source).unwrap();
+    }
+
+    #[test]
+    fn maps_stops_to_exit_codes() {
+        let cases = [
+            (
+                "START      JMP  START\n           END  START\n",
+                LIMIT,
+                "0000: stopped after 10 instructions\n",
+            ),
+            (
+                "START      LDA  4000\n           END  START\n",
+                INVALID_ADDRESS,
+                "0000: invalid address 4000\n",
+            ),
+            (
+                "START      CON  197\n           END  START\n",
+                INVALID_INSTRUCTION,
+                "0000: + 0000 00 03 05 is not an instruction\n",
+            ),
+            (
+                "START      IN   0(16)\n           END  START\n",
+                NO_DEVICE,
+                "0000: no device on unit 16\n",
+            ),
+            (
+                "START      LDA  UNDEFINED\n           END  START\n",
+                FAILURE,
+                "",
+            ),
+        ];
+        for (i, (source, code, message)) in cases.iter().enumerate() {
+            let path = temporary(&format!("stop{}.mixal", i), source);
+            let file = path.to_str().unwrap();
+
+            let result = command(&["run", "--limit", "10", file], "");
+
+            assert_eq!(result.0, *code, "{}", source);
+            if *code == FAILURE {
+                assert_eq!(
+                    result.2,
+                    format!("{}:1: undefined symbol UNDEFINED\n", file)
+                );
+            } else {
+                assert_eq!(result.2, *message);
+            }
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn assembles_empty_programs_instead_of_running_them() {
+        let cases = [
+            ("empty.mixal", "", "line 0: missing END\n"),
+            ("blank.mixal", "\n  \n\n", "FILE:3: missing END\n"),
+        ];
+        for (name, source, error) in cases.iter() {
+            let path = temporary(name, source);
+            let file = path.to_str().unwrap();
+
+            let result = command(&["run", file], "");
+
+            let error = error.replace("FILE", file);
+            assert_eq!(result, (FAILURE, String::new(), error), "{:?}", source);
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn disassembles_and_dumps_programs() {
+        let path = temporary("dump.mixal", HELLO);
+        let file = path.to_str().unwrap();
+
+        assert_eq!(
+            command(&["disasm", file], ""),
+            (
+                SUCCESS,
+                "           ORIG 3000
+           OUT  3003(18)
+L3001      JBUS L3001(18)
+           HLT
+           CON  135582544
+           END  3000
+"
+                .to_string(),
+                String::new()
+            )
+        );
+        assert_eq!(
+            command(&["dump", file], ""),
+            (
+                SUCCESS,
+                "3000  + 3003 00 18 37    787219621  *? Q7  OUT 3003(18)
+3001  + 3001 00 18 34    786695330  *? Q4  JBUS 3001(18)
+3002  + 0000 00 02 05          133     BE  HLT
+3003  + 0517 13 13 16    135582544  HELLO  CON 135582544
+"
+                .to_string(),
+                String::new()
+            )
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn links_modules() {
+        let main = temporary(
+            "main.mixal",
+            "           EXTRN DONE
+           ENTRY START
+START      JMP  DONE
+           END  START
+",
+        );
+        let done = temporary(
+            "done.mixal",
+            "           ENTRY DONE\nDONE       HLT\n           END\n",
+        );
+        let objects: Vec<String> = [&main, &done]
+            .iter()
+            .map(|path| {
+                let (code, object, _) = command(&["asm", "--object", path.to_str().unwrap()], "");
+                assert_eq!(code, SUCCESS);
+                let name = format!("{}.object", path.to_str().unwrap());
+                fs::write(&name, object).unwrap();
+                name
+            })
+            .collect();
+
+        let (code, image, _) = command(&["link", "--origin", "100", &objects[0], &objects[1]], "");
+
+        assert_eq!(code, SUCCESS);
+        assert_eq!(
+            image,
+            "START 0100\nWORD 0100 + 0101 00 00 39\nWORD 0101 + 0000 00 02 05\n"
+        );
+        let (code, _, err) = command(&["run", &objects[0]], "");
+        assert_eq!(code, FAILURE);
+        assert!(
+            err.ends_with("module imports DONE, link it first\n"),
+            "{}",
+            err
+        );
+        for file in objects
+            .iter()
+            .map(String::as_str)
+            .chain(vec![main.to_str().unwrap(), done.to_str().unwrap()])
+        {
+            fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_binary_files_that_are_not_mdk_programs() {
+        let path = temporary("hello.mix", "");
+        fs::write(&path, [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3, 0xff]).unwrap();
+
+        let (code, _, err) = command(&["run", path.to_str().unwrap()], "");
+
+        assert_eq!(code, FAILURE);
+        assert!(
+            err.ends_with("file is compressed, decompress it with gunzip first\n"),
+            "{}",
+            err
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reports_wrong_usage() {
+        let (code, _, err) = command(&["fmt", "--all"], "");
+        assert_eq!(code, USAGE);
+        assert!(err.starts_with("unknown option --all\nusage:"), "{}", err);
+
+        let (code, _, err) = command(&[], "");
+        assert_eq!(code, USAGE);
+        assert!(err.starts_with("usage:"), "{}", err);
+
+        let (code, _, err) = command(&["fmt", "/nonexistent/file.mixal"], "");
+        assert_eq!(code, USAGE);
+        assert!(
+            err.starts_with("cannot read /nonexistent/file.mixal:"),
+            "{}",
+            err
+        );
+
+        let (code, _, err) = command(&["asm", "--listing", "--xref", "a.mixal"], "");
+        assert_eq!(code, USAGE);
+        assert_eq!(err, "--listing and --xref exclude each other\n");
+
+        let (code, _, err) = command(&["run", "--limit"], "");
+        assert_eq!(code, USAGE);
+        assert!(
+            err.starts_with("option --limit needs a value\nusage:"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,959 @@
+//! Interactive debugger that steps the machine one instruction at a time.
+//!
+//! Commands are read line by line, an empty line repeats the previous command. Addresses
+//! are MIXAL expressions over the symbols of the program, where `*` is the program counter:
+//!
+//! ```text
+//! (mix) break LOOP+2
+//! (mix) continue
+//! breakpoint at 3003
+//! =>*3003             J1P 3001
+//! (mix) examine TABLE 3 value
+//! ```
+
+mod condition;
+
+use crate::device::Lines;
+use crate::mixal::assemble;
+use crate::mixal::disassembler::disassemble;
+use crate::mixal::expression::{expression, local_symbol, Locals, Scope};
+use crate::mixal::listing::{dump_line, instruction_form};
+use crate::{
+    Byte, Comparison, Fault, Index, IndexNumber, Jump, Mix, Modification, Register, Sign, Toggle,
+    Watchpoint, Word, BYTE, MEMORY_SIZE, WORD_BYTES,
+};
+use condition::{condition, Condition};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+step [COUNT]                      executes COUNT instructions, 1 by default
+continue                          runs until a breakpoint, HLT or a fault
+break [ADDRESS [hits COUNT] [if CONDITION]]
+                                  stops before the instruction at ADDRESS from the COUNT-th
+                                  time that CONDITION holds there, or lists breakpoints
+clear ADDRESS                     removes the breakpoint at ADDRESS
+watch [ADDRESS]                   stops after a write to ADDRESS, or lists watchpoints
+watch ADDRESS(L:R)                stops when the field of the word at ADDRESS changes
+watch REGISTER = VALUE            stops when a, x, i1-i6 or j becomes VALUE
+unwatch WATCHPOINT                removes the watchpoint written as for watch
+registers                         shows every register
+print REGISTER                    shows a, x, i1-i6, j, overflow or comparison_indicator
+examine ADDRESS [COUNT] [FORMAT]  shows words as value, bytes, fields, chars or instruction,
+                                  or all of them when FORMAT is left out
+set REGISTER VALUE                changes a register, VALUE is on or off for overflow and
+                                  less, equal or greater for comparison_indicator
+set ADDRESS VALUE                 changes a word, VALUE is a number or an instruction
+list [ADDRESS]                    disassembles around ADDRESS, the program counter by default
+quit                              leaves the debugger
+commands may be shortened to s, c, b, r, p, x, l and q
+conditions compare rA, rX, rI1-rI6, rJ, fields mem[ADDRESS](L:R) and numbers with =, !=,
+<, <=, > and >=, and join the comparisons with && and ||, like rI1 = 0 && mem[1000](1:2) > 5";
+
+/// Words that `list` shows before and after the address.
+const CONTEXT: usize = 3;
+
+/// Names of the registers, in the order that `registers` shows them.
+const REGISTERS: [&str; 11] = [
+    "a",
+    "x",
+    "i1",
+    "i2",
+    "i3",
+    "i4",
+    "i5",
+    "i6",
+    "j",
+    "overflow",
+    "comparison_indicator",
+];
+
+/// Machine under the control of the debugger, with the symbols of its program.
+pub struct Debugger {
+    mix: Mix,
+    symbols: BTreeMap<String, i64>,
+    breakpoints: BTreeMap<usize, Breakpoint>,
+}
+
+/// Breakpoint that stops from the `count`-th time that its condition holds when the
+/// program reaches it.
+struct Breakpoint {
+    /// Text of the condition and the condition, a breakpoint without one always stops.
+    condition: Option<(String, Condition)>,
+    /// Times the condition held so far.
+    hits: u64,
+    count: u64,
+}
+
+impl Breakpoint {
+    /// Counts the hit when the condition holds and tells whether to stop.
+    fn reached(&mut self, mix: &Mix) -> bool {
+        if let Some((_, condition)) = &self.condition {
+            if !condition.holds(mix) {
+                return false;
+            }
+        }
+        self.hits += 1;
+        self.hits >= self.count
+    }
+}
+
+impl Debugger {
+    pub fn new(mix: Mix, symbols: BTreeMap<String, i64>) -> Self {
+        Debugger {
+            mix,
+            symbols,
+            breakpoints: BTreeMap::new(),
+        }
+    }
+
+    /// Performs the command and returns its output, which ends with a newline.
+    pub fn command(&mut self, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, arguments) = match words.split_first() {
+            Some((command, arguments)) => (*command, arguments),
+            None => return Ok(String::new()),
+        };
+        match (command, arguments) {
+            ("step" | "s", []) => self.execute(Some(1)),
+            ("step" | "s", [count]) => match count.parse::<u64>() {
+                Ok(count) if count > 0 => self.execute(Some(count)),
+                _ => Err(format!("invalid count {}", count)),
+            },
+            ("continue" | "c", []) => self.execute(None),
+            ("break" | "b", []) => Ok(self
+                .breakpoints
+                .iter()
+                .map(|(&location, breakpoint)| {
+                    let mut line = self.line(location);
+                    if breakpoint.count > 1 {
+                        line.push_str(&format!("  hits {}/{}", breakpoint.hits, breakpoint.count));
+                    }
+                    if let Some((text, _)) = &breakpoint.condition {
+                        line.push_str(&format!("  if {}", text));
+                    }
+                    format!("{}\n", line)
+                })
+                .collect()),
+            ("break" | "b", [address, rest @ ..]) => {
+                let location = self.location(address)?;
+                let breakpoint = self.breakpoint(rest)?;
+                self.breakpoints.insert(location, breakpoint);
+                Ok(format!("breakpoint at {:04}\n", location))
+            }
+            ("clear", [address]) => {
+                let location = self.location(address)?;
+                if self.breakpoints.remove(&location).is_some() {
+                    Ok(format!("cleared breakpoint at {:04}\n", location))
+                } else {
+                    Err(format!("no breakpoint at {:04}", location))
+                }
+            }
+            ("watch", []) => Ok(self
+                .mix
+                .watchpoints
+                .iter()
+                .map(|&watchpoint| format!("{}\n", describe(watchpoint)))
+                .collect()),
+            ("watch", _) => {
+                let watchpoint = self.watchpoint(&arguments.join(" "))?;
+                if !self.mix.watchpoints.contains(&watchpoint) {
+                    self.mix.watchpoints.push(watchpoint);
+                }
+                Ok(format!("watchpoint {}\n", describe(watchpoint)))
+            }
+            ("unwatch", [_, ..]) => {
+                let watchpoint = self.watchpoint(&arguments.join(" "))?;
+                let count = self.mix.watchpoints.len();
+                self.mix
+                    .watchpoints
+                    .retain(|&watched| watched != watchpoint);
+                if self.mix.watchpoints.len() < count {
+                    Ok(format!("removed watchpoint {}\n", describe(watchpoint)))
+                } else {
+                    Err(format!("no watchpoint {}", describe(watchpoint)))
+                }
+            }
+            ("registers" | "r", []) => Ok(REGISTERS
+                .iter()
+                .filter_map(|name| self.register(name))
+                .map(|register| format!("{}\n", register))
+                .collect()),
+            ("print" | "p", [name]) => match self.register(name) {
+                Some(register) => Ok(format!("{}\n", register)),
+                None => Err(format!("unknown register {}", name)),
+            },
+            ("examine" | "x", [address, rest @ ..]) if rest.len() <= 2 => {
+                self.examine(address, rest)
+            }
+            ("set", [target, value @ ..]) if !value.is_empty() => {
+                self.set(target, &value.join(" "))
+            }
+            ("list" | "l", []) => Ok(self.list(self.mix.program_counter)),
+            ("list" | "l", [address]) => {
+                let location = self.location(address)?;
+                Ok(self.list(location))
+            }
+            ("help" | "h", []) => Ok(format!("{}\n", HELP)),
+            _ => Err(format!("unknown command {}, try help", line.trim())),
+        }
+    }
+
+    /// Executes up to `count` instructions, or until the program stops without a count,
+    /// stopping early at a breakpoint or when a watchpoint fires.
+    fn execute(&mut self, count: Option<u64>) -> Result<String, String> {
+        self.runnable()?;
+        let mut mix = std::mem::take(&mut self.mix);
+        let mut executed = 0;
+        let mut at_breakpoint = false;
+        let last = loop {
+            let location = mix.program_counter;
+            let last = (
+                location,
+                mix.memory.get(location).copied().unwrap_or_default(),
+            );
+            mix = mix.step();
+            executed += 1;
+            if mix.halted || mix.fault.is_some() {
+                break last;
+            }
+            at_breakpoint = self
+                .breakpoints
+                .get_mut(&mix.program_counter)
+                .is_some_and(|breakpoint| breakpoint.reached(&mix));
+            let watched = !mix.changes.is_empty();
+            if at_breakpoint || watched || count.is_some_and(|count| executed >= count) {
+                break last;
+            }
+        };
+        self.mix = mix;
+        Ok(self.stopped(last, at_breakpoint))
+    }
+
+    /// Breakpoint of the words after the address of `break`, which are `hits COUNT`
+    /// and `if CONDITION`, both optional.
+    fn breakpoint(&self, words: &[&str]) -> Result<Breakpoint, String> {
+        let (count, words) = match words {
+            ["hits", count, words @ ..] => match count.parse::<u64>() {
+                Ok(count) if count > 0 => (count, words),
+                _ => return Err(format!("invalid count {}", count)),
+            },
+            words => (1, words),
+        };
+        let condition = match words {
+            [] => None,
+            ["if", words @ ..] if !words.is_empty() => {
+                let text = words.join(" ");
+                let parsed = condition(&text, &|text| self.evaluate(text))?;
+                Some((text, parsed))
+            }
+            _ => {
+                return Err(format!(
+                    "expected hits or if instead of {}",
+                    words.join(" ")
+                ))
+            }
+        };
+        Ok(Breakpoint {
+            condition,
+            hits: 0,
+            count,
+        })
+    }
+
+    fn runnable(&self) -> Result<(), String> {
+        if self.mix.halted {
+            Err("the program has halted".to_string())
+        } else if self.mix.fault.is_some() {
+            Err("the program has stopped on a fault".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Why the machine stopped and the instruction it stopped at, `last` is the location
+    /// and word of the last instruction that it executed.
+    fn stopped(&self, last: (usize, Word), at_breakpoint: bool) -> String {
+        let pc = self.mix.program_counter;
+        let mut reason: String = self
+            .mix
+            .changes
+            .iter()
+            .map(|change| {
+                format!(
+                    "watchpoint {}: {} -> {} by {:04} {}\n",
+                    describe(change.watchpoint),
+                    watched_form(change.watchpoint, change.old),
+                    watched_form(change.watchpoint, change.new),
+                    last.0,
+                    disassemble(last.1)
+                )
+            })
+            .collect();
+        if self.mix.halted {
+            return reason + "halted\n";
+        }
+        match fault_message(&self.mix) {
+            Some(message) => reason.push_str(&format!("{:04}: {}\n", pc, message)),
+            None if at_breakpoint => reason.push_str(&format!("breakpoint at {:04}\n", pc)),
+            None => {}
+        }
+        format!("{}{}\n", reason, self.line(pc))
+    }
+
+    /// Disassembly of the words around the location.
+    fn list(&self, location: usize) -> String {
+        let start = location.saturating_sub(CONTEXT);
+        let end = (location + CONTEXT + 1).min(MEMORY_SIZE);
+        (start..end)
+            .map(|location| format!("{}\n", self.line(location)))
+            .collect()
+    }
+
+    /// Location with its label and instruction, marked with `=>` at the program counter
+    /// and with `*` at a breakpoint.
+    fn line(&self, location: usize) -> String {
+        let pointer = if location == self.mix.program_counter {
+            "=>"
+        } else {
+            "  "
+        };
+        let breakpoint = if self.breakpoints.contains_key(&location) {
+            '*'
+        } else {
+            ' '
+        };
+        let label = self
+            .symbols
+            .iter()
+            .find(|(_, &value)| value == location as i64)
+            .map_or("", |(symbol, _)| symbol.as_str());
+        let line = format!(
+            "{}{}{:04}  {:<10} {}",
+            pointer,
+            breakpoint,
+            location,
+            label,
+            disassemble(self.mix.memory[location])
+        );
+        line.trim_end().to_string()
+    }
+
+    fn register(&self, name: &str) -> Option<String> {
+        let mix = &self.mix;
+        let value = match name {
+            "a" => word_form(mix.a),
+            "x" => word_form(mix.x),
+            "i1" => index_form(mix.i1),
+            "i2" => index_form(mix.i2),
+            "i3" => index_form(mix.i3),
+            "i4" => index_form(mix.i4),
+            "i5" => index_form(mix.i5),
+            "i6" => index_form(mix.i6),
+            "j" => index_form(Index::from(Word::from(mix.j))),
+            "overflow" => match mix.overflow {
+                Toggle::On => "on".to_string(),
+                Toggle::Off => "off".to_string(),
+            },
+            "comparison_indicator" => match mix.comparison_indicator {
+                Comparison::Less => "less".to_string(),
+                Comparison::Equal => "equal".to_string(),
+                Comparison::Greater => "greater".to_string(),
+            },
+            _ => return None,
+        };
+        Some(format!("{} = {}", name, value))
+    }
+
+    fn examine(&self, address: &str, rest: &[&str]) -> Result<String, String> {
+        let location = self.location(address)?;
+        let (count, format) = match rest {
+            [] => (1, None),
+            [count] if count.parse::<usize>().is_ok() => (count.parse().unwrap_or(1), None),
+            [format] => (1, Some(*format)),
+            [count, format] => match count.parse::<usize>() {
+                Ok(count) => (count, Some(*format)),
+                Err(_) => return Err(format!("invalid count {}", count)),
+            },
+            _ => return Err(format!("unknown command examine {}", rest.join(" "))),
+        };
+        let mut output = String::new();
+        for location in location..(location + count).min(MEMORY_SIZE) {
+            let word = self.mix.memory[location];
+            let shown = match format {
+                None => dump_line(location, word),
+                Some("value") => format!("{:04}  {}", location, word.value()),
+                Some("bytes") => format!("{:04}  {}", location, word_form(word)),
+                Some("fields") => format!("{:04}  {}", location, instruction_form(word)),
+                Some("chars") => {
+                    let characters: String = word.bytes.iter().map(|byte| byte.char()).collect();
+                    format!("{:04}  {}", location, characters)
+                }
+                Some("instruction") => format!("{:04}  {}", location, disassemble(word)),
+                Some(format) => return Err(format!("unknown format {}", format)),
+            };
+            output.push_str(&shown);
+            output.push('\n');
+        }
+        Ok(output)
+    }
+
+    fn set(&mut self, target: &str, value: &str) -> Result<String, String> {
+        match target {
+            "a" => self.mix.a = word(self.evaluate(value)?)?,
+            "x" => self.mix.x = word(self.evaluate(value)?)?,
+            "i1" | "i2" | "i3" | "i4" | "i5" | "i6" => {
+                let index = index(self.evaluate(value)?)?;
+                let mix = &mut self.mix;
+                match target {
+                    "i1" => mix.i1 = index,
+                    "i2" => mix.i2 = index,
+                    "i3" => mix.i3 = index,
+                    "i4" => mix.i4 = index,
+                    "i5" => mix.i5 = index,
+                    _ => mix.i6 = index,
+                }
+            }
+            "j" => match self.evaluate(value)? {
+                j @ 0..=4095 => self.mix.j = Jump::from(word(j)?),
+                j => return Err(format!("{} does not fit into rJ", j)),
+            },
+            "overflow" => {
+                self.mix.overflow = match value {
+                    "on" => Toggle::On,
+                    "off" => Toggle::Off,
+                    _ => return Err(format!("overflow is on or off, not {}", value)),
+                }
+            }
+            "comparison_indicator" => {
+                self.mix.comparison_indicator = match value {
+                    "less" => Comparison::Less,
+                    "equal" => Comparison::Equal,
+                    "greater" => Comparison::Greater,
+                    _ => {
+                        let message =
+                            format!("comparison is less, equal or greater, not {}", value);
+                        return Err(message);
+                    }
+                }
+            }
+            address => {
+                let location = self.location(address)?;
+                let contents = match self.evaluate(value) {
+                    Ok(value) => word(value)?,
+                    Err(_) => self.instruction(location, value)?,
+                };
+                self.mix.memory[location] = contents;
+                return Ok(format!("{}\n", dump_line(location, contents)));
+            }
+        }
+        Ok(format!("{}\n", self.register(target).unwrap_or_default()))
+    }
+
+    /// Watchpoint written as `ADDRESS`, `ADDRESS(L:R)` or `REGISTER = VALUE`.
+    fn watchpoint(&self, text: &str) -> Result<Watchpoint, String> {
+        if let Some((name, value)) = text.split_once('=') {
+            let register = register_named(name.trim())
+                .ok_or_else(|| format!("cannot watch register {}", name.trim()))?;
+            return Ok(Watchpoint::Register(register, self.evaluate(value.trim())?));
+        }
+        match text.strip_suffix(')').and_then(|text| text.split_once('(')) {
+            Some((address, text)) => {
+                let location = self.location(address.trim())?;
+                let field = match self.evaluate(text)? {
+                    value @ 0..=63 => Modification::from(Byte::new(value as u8)),
+                    _ => return Err(format!("invalid field {}", text)),
+                };
+                if !field.is_valid() {
+                    return Err(format!("invalid field {}", text));
+                }
+                Ok(Watchpoint::Field(location, field))
+            }
+            None => Ok(Watchpoint::Write(self.location(text)?)),
+        }
+    }
+
+    /// Word of the instruction assembled at the location, with the symbols of the program.
+    fn instruction(&self, location: usize, text: &str) -> Result<Word, String> {
+        let mut source: String = self
+            .symbols
+            .iter()
+            .filter(|(symbol, _)| local_symbol(symbol).is_none())
+            .map(|(symbol, value)| format!("{:<10} EQU  {}\n", symbol, value))
+            .collect();
+        source.push_str(&format!("{:<10} ORIG {}\n", "", location));
+        source.push_str(&format!("{:<10} {}\n", "", text));
+        source.push_str(&format!("{:<10} END  0\n", ""));
+        match assemble(&source) {
+            Ok(program) => program
+                .image
+                .words
+                .get(&location)
+                .copied()
+                .ok_or_else(|| format!("{} is not an instruction", text)),
+            Err(errors) => Err(errors
+                .into_iter()
+                .next()
+                .map_or_else(String::new, |error| error.message)),
+        }
+    }
+
+    /// Location in memory of the address expression.
+    fn location(&self, address: &str) -> Result<usize, String> {
+        match self.evaluate(address)? {
+            location @ 0..=3999 => Ok(location as usize),
+            location => Err(format!("invalid address {}", location)),
+        }
+    }
+
+    fn evaluate(&self, text: &str) -> Result<i64, String> {
+        let scope = Scope {
+            symbols: &self.symbols,
+            locals: &Locals::default(),
+            line: 0,
+            location: self.mix.program_counter as i64,
+        };
+        expression(text, &scope)
+    }
+}
+
+/// Reads commands until `quit` or the end of the input. Lines printed by the program
+/// in `printed` are shown as they appear.
+pub fn debug(
+    debugger: &mut Debugger,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+    printed: &Lines,
+) -> io::Result<()> {
+    let mut previous = String::new();
+    let mut shown = 0;
+    loop {
+        write!(out, "(mix) ")?;
+        out.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return writeln!(out);
+        }
+        let line = match line.trim() {
+            "" => previous,
+            line => line.to_string(),
+        };
+        if matches!(line.as_str(), "quit" | "q") {
+            return Ok(());
+        }
+        let reply = debugger.command(&line);
+        let lines = printed.lines();
+        for printed in lines.iter().skip(shown) {
+            writeln!(out, "{}", printed)?;
+        }
+        shown = lines.len();
+        match reply {
+            Ok(output) => write!(out, "{}", output)?,
+            Err(message) => writeln!(out, "{}", message)?,
+        }
+        previous = line;
+    }
+}
+
+/// Watchpoint in the form that `watch` reads.
+fn describe(watchpoint: Watchpoint) -> String {
+    match watchpoint {
+        Watchpoint::Write(location) => format!("{:04}", location),
+        Watchpoint::Field(location, Modification::Field { l, r }) => {
+            format!("{:04}({}:{})", location, l, r)
+        }
+        Watchpoint::Register(register, value) => {
+            let name = match register {
+                Register::A => "a".to_string(),
+                Register::X => "x".to_string(),
+                Register::I(index) => format!("i{}", index as u8),
+                Register::J => "j".to_string(),
+            };
+            format!("{} = {}", name, value)
+        }
+    }
+}
+
+fn register_named(name: &str) -> Option<Register> {
+    let register = match name {
+        "a" => Register::A,
+        "x" => Register::X,
+        "i1" => Register::I(IndexNumber::I1),
+        "i2" => Register::I(IndexNumber::I2),
+        "i3" => Register::I(IndexNumber::I3),
+        "i4" => Register::I(IndexNumber::I4),
+        "i5" => Register::I(IndexNumber::I5),
+        "i6" => Register::I(IndexNumber::I6),
+        "j" => Register::J,
+        _ => return None,
+    };
+    Some(register)
+}
+
+/// Why the machine stopped on a fault.
+pub fn fault_message(mix: &Mix) -> Option<String> {
+    let message = match mix.fault.as_ref()? {
+        Fault::InvalidInstruction(location) => format!(
+            "{} is not an instruction",
+            instruction_form(mix.memory[*location])
+        ),
+        Fault::InvalidAddress(address) => format!("invalid address {}", address),
+        Fault::NoDevice(unit) => format!("no device on unit {}", unit),
+        Fault::Device(unit, error) => format!("unit {}: {}", unit, error),
+    };
+    Some(message)
+}
+
+fn word(value: i64) -> Result<Word, String> {
+    if value.unsigned_abs() >= (BYTE as u64).pow(WORD_BYTES as u32) {
+        return Err(format!("{} does not fit into a word", value));
+    }
+    let sign = if value < 0 { Sign::Minus } else { Sign::Plus };
+    Ok(Word::from_magnitude(sign, value.unsigned_abs()))
+}
+
+fn index(value: i64) -> Result<Index, String> {
+    if value.unsigned_abs() >= (BYTE as u64).pow(2) {
+        return Err(format!("{} does not fit into an index register", value));
+    }
+    word(value).map(Index::from)
+}
+
+/// Sign and bytes of the word with its value, like `+ 00 00 00 01 02 (66)`.
+fn word_form(word: Word) -> String {
+    bytes_form(word.sign, &word.bytes, word.value())
+}
+
+/// Value that the watchpoint watches, in the form that `print` shows for its register.
+fn watched_form(watchpoint: Watchpoint, word: Word) -> String {
+    match watchpoint {
+        Watchpoint::Register(Register::I(_), _) | Watchpoint::Register(Register::J, _) => {
+            index_form(Index::from(word))
+        }
+        _ => word_form(word),
+    }
+}
+
+fn index_form(index: Index) -> String {
+    bytes_form(index.sign, &index.bytes, Word::from(index).value())
+}
+
+fn bytes_form(sign: Sign, bytes: &[Byte], value: i64) -> String {
+    let sign = if sign == Sign::Minus { '-' } else { '+' };
+    let bytes: Vec<String> = bytes.iter().map(|byte| format!("{:02}", byte.0)).collect();
+    format!("{} {} ({})", sign, bytes.join(" "), value)
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::device::{self, LinePrinter, Paging};
+
+    const SUM: &str = "           ORIG 3000
+START      ENT1 3
+LOOP       ADD  TABLE-1,1
+           DEC1 1
+           J1P  LOOP
+           STA  SUM
+           HLT
+TABLE      CON  10
+           CON  20
+           CON  -5
+SUM        CON  0
+           END  START";
+
+    fn debugger(source: &str) -> Debugger {
+        let program = assemble(source).unwrap();
+        let mut mix = Mix::default();
+        mix.load_image(&program.image);
+        Debugger::new(mix, program.symbols)
+    }
+
+    fn commands(debugger: &mut Debugger, lines: &[&str]) -> Vec<Result<String, String>> {
+        lines.iter().map(|line| debugger.command(line)).collect()
+    }
+
+    #[test]
+    fn steps_and_stops_at_breakpoints() {
+        let mut debugger = debugger(SUM);
+
+        assert_eq!(
+            commands(
+                &mut debugger,
+                &["step", "break LOOP+2", "b 3005", "b", "c", "p i1", "p a"]
+            ),
+            vec![
+                Ok("=> 3001  LOOP       ADD 3005,1\n".to_string()),
+                Ok("breakpoint at 3003\n".to_string()),
+                Ok("breakpoint at 3005\n".to_string()),
+                Ok("  *3003             J1P 3001\n  *3005             HLT\n".to_string()),
+                Ok("breakpoint at 3003\n=>*3003             J1P 3001\n".to_string()),
+                Ok("i1 = + 00 02 (2)\n".to_string()),
+                Ok("a = - 00 00 00 00 05 (-5)\n".to_string()),
+            ]
+        );
+        assert_eq!(
+            commands(
+                &mut debugger,
+                &["clear *", "clear 3003", "step 100", "c", "s", "x SUM value"]
+            ),
+            vec![
+                Ok("cleared breakpoint at 3003\n".to_string()),
+                Err("no breakpoint at 3003".to_string()),
+                Ok("breakpoint at 3005\n=>*3005             HLT\n".to_string()),
+                Ok("halted\n".to_string()),
+                Err("the program has halted".to_string()),
+                Ok("3009  25\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn shows_registers_memory_and_disassembly() {
+        let mut debugger = debugger(SUM);
+        debugger.command("step 3").unwrap();
+
+        assert_eq!(
+            debugger.command("registers"),
+            Ok("a = - 00 00 00 00 05 (-5)
+x = + 00 00 00 00 00 (0)
+i1 = + 00 02 (2)
+i2 = + 00 00 (0)
+i3 = + 00 00 (0)
+i4 = + 00 00 (0)
+i5 = + 00 00 (0)
+i6 = + 00 00 (0)
+j = + 00 00 (0)
+overflow = off
+comparison_indicator = equal
+"
+            .to_string())
+        );
+        assert_eq!(
+            debugger.command("list"),
+            Ok("   3000  START      ENT1 3
+   3001  LOOP       ADD 3005,1
+   3002             DEC1 1
+=> 3003             J1P 3001
+   3004             STA 3009
+   3005             HLT
+   3006  TABLE      LD2 0(0:0)
+"
+            .to_string())
+        );
+        assert_eq!(
+            commands(
+                &mut debugger,
+                &[
+                    "x TABLE",
+                    "x TABLE+2 2 bytes",
+                    "x 3001 fields",
+                    "x 3001 instruction",
+                    "x 3001 chars",
+                    "x 3999 5 value",
+                    "x 3001 octal",
+                ]
+            ),
+            vec![
+                Ok("3006  + 0000 00 00 10           10      Δ  LD2 0(0:0)\n".to_string()),
+                Ok("3008  - 00 00 00 00 05 (-5)\n3009  + 00 00 00 00 00 (0)\n".to_string()),
+                Ok("3001  + 3005 01 05 01\n".to_string()),
+                Ok("3001  ADD 3005,1\n".to_string()),
+                Ok("3001  *?AEA\n".to_string()),
+                Ok("3999  0\n".to_string()),
+                Err("unknown format octal".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn changes_registers_and_memory() {
+        let mut debugger = debugger(SUM);
+
+        assert_eq!(
+            commands(
+                &mut debugger,
+                &[
+                    "set a -100",
+                    "set i6 SUM",
+                    "set i1 4096",
+                    "set j 3000",
+                    "set overflow on",
+                    "set comparison_indicator greater",
+                    "set SUM 1000000",
+                    "set TABLE JMP START,2",
+                    "set 3007 FOO 1",
+                    "set 4000 1",
+                    "print rA",
+                    "frobnicate",
+                ]
+            ),
+            vec![
+                Ok("a = - 00 00 00 01 36 (-100)\n".to_string()),
+                Ok("i6 = + 47 01 (3009)\n".to_string()),
+                Err("4096 does not fit into an index register".to_string()),
+                Ok("j = + 46 56 (3000)\n".to_string()),
+                Ok("overflow = on\n".to_string()),
+                Ok("comparison_indicator = greater\n".to_string()),
+                Ok("3009  + 0003 52 09 00      1000000   C@I   CON 1000000\n".to_string()),
+                Ok("3006  + 3000 02 00 39    786440231  *?B 9  JMP 3000,2\n".to_string()),
+                Err("unknown operation FOO".to_string()),
+                Err("invalid address 4000".to_string()),
+                Err("unknown register rA".to_string()),
+                Err("unknown command frobnicate, try help".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_when_watchpoints_fire() {
+        let mut debugger = debugger(SUM);
+
+        assert_eq!(
+            commands(
+                &mut debugger,
+                &[
+                    "watch i1 = 1",
+                    "watch SUM(4:5)",
+                    "watch TABLE",
+                    "watch",
+                    "continue",
+                    "p a",
+                    "unwatch i1=1",
+                    "unwatch i1 = 1",
+                    "c",
+                    "c",
+                ]
+            ),
+            vec![
+                Ok("watchpoint i1 = 1\n".to_string()),
+                Ok("watchpoint 3009(4:5)\n".to_string()),
+                Ok("watchpoint 3006\n".to_string()),
+                Ok("i1 = 1\n3009(4:5)\n3006\n".to_string()),
+                Ok("watchpoint i1 = 1: + 00 02 (2) -> + 00 01 (1) by 3002 DEC1 1
+=> 3003             J1P 3001
+"
+                .to_string()),
+                Ok("a = + 00 00 00 00 15 (15)\n".to_string()),
+                Ok("removed watchpoint i1 = 1\n".to_string()),
+                Err("no watchpoint i1 = 1".to_string()),
+                Ok("watchpoint 3009(4:5): + 00 00 00 00 00 (0) -> + 00 00 00 00 25 (25) by 3004 STA 3009
+=> 3005             HLT
+"
+                .to_string()),
+                Ok("halted\n".to_string()),
+            ]
+        );
+        assert_eq!(
+            commands(
+                &mut debugger,
+                &["watch SUM(5:4)", "watch pc = 3000", "watch 4000(0:5)"]
+            ),
+            vec![
+                Err("invalid field 5:4".to_string()),
+                Err("cannot watch register pc".to_string()),
+                Err("invalid address 4000".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_breakpoints_when_conditions_hold() {
+        let mut debugger = debugger(SUM);
+
+        assert_eq!(
+            commands(
+                &mut debugger,
+                &[
+                    "break LOOP if rI1 = 1 && mem[SUM] = 0",
+                    "break 3002 hits 2",
+                    "b",
+                    "c",
+                    "p a",
+                    "c",
+                    "p i1",
+                    "b",
+                ]
+            ),
+            vec![
+                Ok("breakpoint at 3001\n".to_string()),
+                Ok("breakpoint at 3002\n".to_string()),
+                Ok("  *3001  LOOP       ADD 3005,1  if rI1 = 1 && mem[SUM] = 0
+  *3002             DEC1 1  hits 0/2
+"
+                .to_string()),
+                Ok("breakpoint at 3002\n=>*3002             DEC1 1\n".to_string()),
+                Ok("a = + 00 00 00 00 15 (15)\n".to_string()),
+                Ok("breakpoint at 3001\n=>*3001  LOOP       ADD 3005,1\n".to_string()),
+                Ok("i1 = + 00 01 (1)\n".to_string()),
+                Ok("=>*3001  LOOP       ADD 3005,1  if rI1 = 1 && mem[SUM] = 0
+  *3002             DEC1 1  hits 2/2
+"
+                .to_string()),
+            ]
+        );
+        assert_eq!(
+            commands(
+                &mut debugger,
+                &[
+                    "break 3003 hits 0",
+                    "break 3003 when rA = 0",
+                    "break 3003 if rA",
+                    "break 3003 if",
+                ]
+            ),
+            vec![
+                Err("invalid count 0".to_string()),
+                Err("expected hits or if instead of when rA = 0".to_string()),
+                Err("missing comparison at the end of rA".to_string()),
+                Err("expected hits or if instead of if".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_commands_and_shows_printed_lines() {
+        let program = assemble(
+            "           ORIG 3000
+START      OUT  TEXT(18)
+           HLT
+TEXT       ALF  HELLO
+           ORIG TEXT+24
+           END  START",
+        )
+        .unwrap();
+        let printed = Lines::default();
+        let mut mix = Mix::default();
+        mix.attach(
+            device::PRINTER,
+            LinePrinter::new(printed.clone(), 60, Paging::Separator),
+        );
+        mix.load_image(&program.image);
+        let mut debugger = Debugger::new(mix, program.symbols);
+        let mut out = vec![];
+
+        debug(
+            &mut debugger,
+            &mut "list START\nstep\n\n\nquit\nstep\n".as_bytes(),
+            &mut out,
+            &printed,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "(mix)    2997             NOP
+   2998             NOP
+   2999             NOP
+=> 3000  START      OUT 3002(18)
+   3001             HLT
+   3002  TEXT       CON 135582544
+   3003             NOP
+(mix) HELLO
+=> 3001             HLT
+(mix) halted
+(mix) the program has halted
+(mix) "
+        );
+    }
+}
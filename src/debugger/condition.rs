@@ -0,0 +1,325 @@
+//! Conditions of breakpoints, like `rI1 = 0 && mem[1000](1:2) > 5`.
+//!
+//! Operands are the registers rA, rX, rI1–rI6 and rJ, fields of words in memory written
+//! `mem[ADDRESS](L:R)` with the field (0:5) by default, and MIXAL expressions. Operands
+//! are compared with `=`, `!=`, `<`, `<=`, `>` and `>=`, comparisons are joined with `&&`,
+//! which binds tighter than `||`, and grouped with parentheses.
+
+use crate::mixal::expression::field_specification;
+use crate::{IndexNumber, Mix, Modification, Register, MEMORY_SIZE, WORD_BYTES};
+use std::cmp::Ordering;
+
+#[derive(Debug, PartialEq)]
+pub enum Condition {
+    Compare(Operand, Relation, Operand),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    pub fn holds(&self, mix: &Mix) -> bool {
+        match self {
+            Condition::Compare(left, relation, right) => {
+                relation.holds(left.value(mix).cmp(&right.value(mix)))
+            }
+            Condition::And(left, right) => left.holds(mix) && right.holds(mix),
+            Condition::Or(left, right) => left.holds(mix) || right.holds(mix),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Operand {
+    Number(i64),
+    Register(Register),
+    /// Field of the word at the location.
+    Memory(usize, Modification),
+}
+
+impl Operand {
+    fn value(&self, mix: &Mix) -> i64 {
+        match *self {
+            Operand::Number(value) => value,
+            Operand::Register(register) => mix.register(register).value(),
+            Operand::Memory(location, field) => mix.memory[location].slice(field).value(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Relation {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Relation {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Relation::Equal => ordering == Ordering::Equal,
+            Relation::NotEqual => ordering != Ordering::Equal,
+            Relation::Less => ordering == Ordering::Less,
+            Relation::LessOrEqual => ordering != Ordering::Greater,
+            Relation::Greater => ordering == Ordering::Greater,
+            Relation::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// Parses the condition, `evaluate` gives the values of MIXAL expressions.
+pub fn condition(
+    text: &str,
+    evaluate: &dyn Fn(&str) -> Result<i64, String>,
+) -> Result<Condition, String> {
+    let mut parser = Parser {
+        text,
+        chars: text.chars().collect(),
+        position: 0,
+        evaluate,
+    };
+    let condition = parser.or()?;
+    match parser.peek() {
+        None => Ok(condition),
+        Some(c) => Err(parser.unexpected(c)),
+    }
+}
+
+/// Characters that end an operand.
+const DELIMITERS: &str = "=<>!&|()[]";
+
+struct Parser<'a> {
+    text: &'a str,
+    chars: Vec<char>,
+    position: usize,
+    evaluate: &'a dyn Fn(&str) -> Result<i64, String>,
+}
+
+impl Parser<'_> {
+    fn or(&mut self) -> Result<Condition, String> {
+        let mut condition = self.and()?;
+        while self.eat("||") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> Result<Condition, String> {
+        let mut condition = self.comparison()?;
+        while self.eat("&&") {
+            condition = Condition::And(Box::new(condition), Box::new(self.comparison()?));
+        }
+        Ok(condition)
+    }
+
+    fn comparison(&mut self) -> Result<Condition, String> {
+        if self.eat("(") {
+            let condition = self.or()?;
+            self.expect(')')?;
+            return Ok(condition);
+        }
+        let left = self.operand()?;
+        let relation = self.relation()?;
+        let right = self.operand()?;
+        Ok(Condition::Compare(left, relation, right))
+    }
+
+    fn relation(&mut self) -> Result<Relation, String> {
+        let relations = [
+            ("!=", Relation::NotEqual),
+            ("<=", Relation::LessOrEqual),
+            (">=", Relation::GreaterOrEqual),
+            ("=", Relation::Equal),
+            ("<", Relation::Less),
+            (">", Relation::Greater),
+        ];
+        for (operator, relation) in relations {
+            if self.eat(operator) {
+                return Ok(relation);
+            }
+        }
+        Err(match self.peek() {
+            Some(c) => self.unexpected(c),
+            None => format!("missing comparison at the end of {}", self.text),
+        })
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        self.skip_whitespace();
+        let start = self.position;
+        while matches!(self.peek_raw(), Some(c) if !c.is_whitespace() && !DELIMITERS.contains(c)) {
+            self.position += 1;
+        }
+        let atom: String = self.chars[start..self.position].iter().collect();
+        match atom.as_str() {
+            "" => Err(match self.peek() {
+                Some(c) => self.unexpected(c),
+                None => format!("missing operand at the end of {}", self.text),
+            }),
+            "mem" => {
+                self.expect('[')?;
+                let address = self.until(']')?;
+                let location = match (self.evaluate)(&address)? {
+                    location if (0..MEMORY_SIZE as i64).contains(&location) => location as usize,
+                    location => return Err(format!("invalid address {}", location)),
+                };
+                let field = if self.eat("(") {
+                    field_specification((self.evaluate)(&self.until(')')?)?)?
+                } else {
+                    Modification::field(0, WORD_BYTES)
+                };
+                Ok(Operand::Memory(location, field))
+            }
+            "rA" => Ok(Operand::Register(Register::A)),
+            "rX" => Ok(Operand::Register(Register::X)),
+            "rI1" => Ok(Operand::Register(Register::I(IndexNumber::I1))),
+            "rI2" => Ok(Operand::Register(Register::I(IndexNumber::I2))),
+            "rI3" => Ok(Operand::Register(Register::I(IndexNumber::I3))),
+            "rI4" => Ok(Operand::Register(Register::I(IndexNumber::I4))),
+            "rI5" => Ok(Operand::Register(Register::I(IndexNumber::I5))),
+            "rI6" => Ok(Operand::Register(Register::I(IndexNumber::I6))),
+            "rJ" => Ok(Operand::Register(Register::J)),
+            expression => (self.evaluate)(expression).map(Operand::Number),
+        }
+    }
+
+    /// Text up to the character, which is skipped.
+    fn until(&mut self, end: char) -> Result<String, String> {
+        let start = self.position;
+        while self.peek_raw() != Some(end) {
+            if self.peek_raw().is_none() {
+                return Err(format!("missing {:?} at the end of {}", end, self.text));
+            }
+            self.position += 1;
+        }
+        self.position += 1;
+        Ok(self.chars[start..self.position - 1].iter().collect())
+    }
+
+    /// Skips the operator when it comes next.
+    fn eat(&mut self, operator: &str) -> bool {
+        self.skip_whitespace();
+        let matches = operator
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.position + i) == Some(&c));
+        if matches {
+            self.position += operator.chars().count();
+        }
+        matches
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.unexpected(c)),
+            None => Err(format!(
+                "missing {:?} at the end of {}",
+                expected, self.text
+            )),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek_raw(), Some(c) if c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    /// Next character that is not whitespace.
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.peek_raw()
+    }
+
+    fn peek_raw(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn unexpected(&self, c: char) -> String {
+        format!("unexpected {:?} in {}", c, self.text)
+    }
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::mixal::expression::{expression, Locals, Scope};
+    use crate::{Sign, Word};
+
+    fn evaluate(text: &str) -> Result<i64, String> {
+        let symbols = vec![("TABLE".to_string(), 1000)].into_iter().collect();
+        let scope = Scope {
+            symbols: &symbols,
+            locals: &Locals::default(),
+            line: 0,
+            location: 0,
+        };
+        expression(text, &scope)
+    }
+
+    fn holds(text: &str, mix: &Mix) -> Result<bool, String> {
+        condition(text, &evaluate).map(|condition| condition.holds(mix))
+    }
+
+    #[test]
+    fn compares_registers_and_fields_of_memory() {
+        let mut mix = Mix {
+            a: Word::from_magnitude(Sign::Minus, 7),
+            ..Mix::default()
+        };
+        mix.memory[1000] = Word::new(Sign::Minus, 0, 6, 1, 2, 3);
+        mix.memory[1001] = Word::new(Sign::Plus, 0, 0, 0, 0, 10);
+
+        assert_eq!(holds("rI1 = 0 && mem[1000](1:2) > 5", &mix), Ok(true));
+        assert_eq!(holds("rI1=0&&mem[TABLE](1:2)>6", &mix), Ok(false));
+        assert_eq!(holds("mem[TABLE] < -1000", &mix), Ok(true));
+        assert_eq!(holds("mem[TABLE+1](5:5) >= 10", &mix), Ok(true));
+        assert_eq!(holds("rA != -7 || rX <= 0", &mix), Ok(true));
+        assert_eq!(holds("rA = -7 && (rJ > 0 || rI6 < 0)", &mix), Ok(false));
+        assert_eq!(holds("rA = -7 && rJ > 0 || rI6 = 0", &mix), Ok(true));
+    }
+
+    #[test]
+    fn invalid_conditions() {
+        let mix = Mix::default();
+
+        assert_eq!(
+            holds("rA", &mix),
+            Err("missing comparison at the end of rA".to_string())
+        );
+        assert_eq!(
+            holds("rA == 0", &mix),
+            Err("unexpected '=' in rA == 0".to_string())
+        );
+        assert_eq!(
+            holds("rB = 0", &mix),
+            Err("undefined symbol rB".to_string())
+        );
+        assert_eq!(
+            holds("(rA = 0", &mix),
+            Err("missing ')' at the end of (rA = 0".to_string())
+        );
+        assert_eq!(
+            holds("mem[4000] = 0", &mix),
+            Err("invalid address 4000".to_string())
+        );
+        assert_eq!(
+            holds("mem[1000(1:2) = 0", &mix),
+            Err("missing ']' at the end of mem[1000(1:2) = 0".to_string())
+        );
+        assert_eq!(
+            holds("mem[1000](5:1) = 0", &mix),
+            Err("field (5:1) is not a field specification".to_string())
+        );
+        assert_eq!(
+            holds("rA = 0 rX", &mix),
+            Err("unexpected 'r' in rA = 0 rX".to_string())
+        );
+    }
+}
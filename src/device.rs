@@ -0,0 +1,647 @@
+use super::{Byte, Word};
+use std::cell::RefCell;
+use std::io::{self, BufRead, Seek, SeekFrom, Write};
+use std::rc::Rc;
+
+pub const CARD_READER: u8 = 16;
+pub const CARD_PUNCH: u8 = 17;
+pub const PRINTER: u8 = 18;
+pub const TYPEWRITER: u8 = 19;
+pub const PAPER_TAPE: u8 = 20;
+
+/// Peripheral attached to one of the input-output units.
+pub trait Device {
+    /// Number of words transferred by a single IN or OUT.
+    fn block_size(&self) -> usize;
+
+    /// Receives rX before every operation, disks take the block number from it.
+    fn position(&mut self, _x: Word) {}
+
+    /// Reads the next block of the device into memory, as IN does.
+    fn input(&mut self, _block: &mut [Word]) -> io::Result<()> {
+        Err(unsupported("input"))
+    }
+
+    /// Writes a block of memory to the device, as OUT does.
+    fn output(&mut self, _block: &[Word]) -> io::Result<()> {
+        Err(unsupported("output"))
+    }
+
+    /// Performs IOC with effective address `m`.
+    fn control(&mut self, _m: i16) -> io::Result<()> {
+        Err(unsupported("control"))
+    }
+}
+
+fn unsupported(operation: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("device does not support {}", operation),
+    )
+}
+
+/// Characters of a block, five per word, signs are ignored.
+fn text(block: &[Word]) -> String {
+    block
+        .iter()
+        .flat_map(|word| word.bytes.iter())
+        .map(|byte| byte.char())
+        .collect()
+}
+
+/// Reads one line of text into a block, five characters per word.
+///
+/// Lowercase letters are read as uppercase, short lines are padded with spaces.
+fn read_text<R: BufRead>(input: &mut R, block: &mut [Word]) -> io::Result<()> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "no more input",
+        ));
+    }
+    let line = line.trim_end_matches(&['\n', '\r'][..]);
+    let capacity = block.len() * 5;
+    if line.chars().count() > capacity {
+        return Err(invalid_data(format!(
+            "line is longer than {} characters: {:?}",
+            capacity, line
+        )));
+    }
+    let mut bytes = line
+        .chars()
+        .map(|c| {
+            Byte::from_char(c.to_ascii_uppercase())
+                .ok_or_else(|| invalid_data(format!("{:?} is not a MIX character", c)))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    bytes.resize(capacity, Byte::default());
+    for (word, bytes) in block.iter_mut().zip(bytes.chunks(5)) {
+        *word = Word::default();
+        word.bytes.copy_from_slice(bytes);
+    }
+    Ok(())
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Words of a tape or a disk kept in memory, shared with whoever inspects them.
+pub type Words = Rc<RefCell<Vec<Word>>>;
+
+/// Text output of a device kept in memory, shared with whoever inspects it.
+#[derive(Debug, Clone, Default)]
+pub struct Lines(Rc<RefCell<Vec<u8>>>);
+
+impl Lines {
+    pub fn lines(&self) -> Vec<String> {
+        String::from_utf8_lossy(&self.0.borrow())
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Text written since the last time it was taken.
+    pub fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut self.0.borrow_mut())
+    }
+}
+
+impl Write for Lines {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Paging {
+    /// Pages are separated by a form feed character.
+    FormFeed,
+    /// Pages are separated by a line of dashes.
+    Separator,
+}
+
+/// Line printer, prints blocks of 24 words as lines of 120 characters.
+///
+/// A new page starts after `page_length` lines or on IOC 0.
+pub struct LinePrinter<W> {
+    out: W,
+    page_length: usize,
+    paging: Paging,
+    line: usize,
+}
+
+impl<W: Write> LinePrinter<W> {
+    pub fn new(out: W, page_length: usize, paging: Paging) -> Self {
+        Self {
+            out,
+            page_length,
+            paging,
+            line: 0,
+        }
+    }
+
+    fn new_page(&mut self) -> io::Result<()> {
+        match self.paging {
+            Paging::FormFeed => write!(self.out, "\x0c")?,
+            Paging::Separator => writeln!(self.out, "{}", "-".repeat(120))?,
+        }
+        self.line = 0;
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Device for LinePrinter<W> {
+    fn block_size(&self) -> usize {
+        24
+    }
+
+    fn output(&mut self, block: &[Word]) -> io::Result<()> {
+        if self.line >= self.page_length {
+            self.new_page()?;
+        }
+        writeln!(self.out, "{}", text(block).trim_end())?;
+        self.line += 1;
+        self.out.flush()
+    }
+
+    /// Skips to the top of the next page, does nothing when the current page is still empty.
+    fn control(&mut self, m: i16) -> io::Result<()> {
+        match m {
+            0 if self.line == 0 => Ok(()),
+            0 => self.new_page(),
+            _ => Err(invalid_input(format!(
+                "printer can only skip to a new page, got IOC {}",
+                m
+            ))),
+        }
+    }
+}
+
+/// Typewriter, reads and prints blocks of 14 words as lines of 70 characters.
+pub struct Typewriter<R, W> {
+    input: R,
+    out: W,
+}
+
+impl<R: BufRead, W: Write> Typewriter<R, W> {
+    pub fn new(input: R, out: W) -> Self {
+        Self { input, out }
+    }
+}
+
+impl<R: BufRead, W: Write> Device for Typewriter<R, W> {
+    fn block_size(&self) -> usize {
+        14
+    }
+
+    fn input(&mut self, block: &mut [Word]) -> io::Result<()> {
+        read_text(&mut self.input, block)
+    }
+
+    fn output(&mut self, block: &[Word]) -> io::Result<()> {
+        writeln!(self.out, "{}", text(block).trim_end())?;
+        self.out.flush()
+    }
+}
+
+/// Paper tape reader, reads blocks of 14 words, one line of text per block.
+pub struct PaperTape<R> {
+    input: R,
+}
+
+impl<R: BufRead + Seek> PaperTape<R> {
+    pub fn new(input: R) -> Self {
+        Self { input }
+    }
+}
+
+impl<R: BufRead + Seek> Device for PaperTape<R> {
+    fn block_size(&self) -> usize {
+        14
+    }
+
+    fn input(&mut self, block: &mut [Word]) -> io::Result<()> {
+        read_text(&mut self.input, block)
+    }
+
+    /// Rewinds the tape to the beginning.
+    fn control(&mut self, m: i16) -> io::Result<()> {
+        match m {
+            0 => self.input.seek(SeekFrom::Start(0)).map(|_| ()),
+            _ => Err(invalid_input(format!(
+                "paper tape can only be rewound, got IOC {}",
+                m
+            ))),
+        }
+    }
+}
+
+/// Magnetic tape, transfers blocks of 100 words.
+///
+/// Writing a block erases the rest of the tape.
+pub struct Tape {
+    words: Words,
+    position: usize,
+}
+
+impl Tape {
+    pub fn new(words: Words) -> Self {
+        Self { words, position: 0 }
+    }
+}
+
+impl Device for Tape {
+    fn block_size(&self) -> usize {
+        100
+    }
+
+    fn input(&mut self, block: &mut [Word]) -> io::Result<()> {
+        let words = self.words.borrow();
+        let end = self.position + block.len();
+        if end > words.len() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "end of tape"));
+        }
+        block.copy_from_slice(&words[self.position..end]);
+        self.position = end;
+        Ok(())
+    }
+
+    fn output(&mut self, block: &[Word]) -> io::Result<()> {
+        let mut words = self.words.borrow_mut();
+        words.truncate(self.position);
+        words.extend_from_slice(block);
+        self.position += block.len();
+        Ok(())
+    }
+
+    /// Rewinds the tape when `m` is zero, otherwise skips `m` blocks forward or backward.
+    fn control(&mut self, m: i16) -> io::Result<()> {
+        let position = match m {
+            0 => 0,
+            _ => self.position as i64 + m as i64 * self.block_size() as i64,
+        };
+        self.position = position.max(0).min(self.words.borrow().len() as i64) as usize;
+        Ok(())
+    }
+}
+
+/// Disk or drum, transfers blocks of 100 words at the block number in rX.
+pub struct Disk {
+    words: Words,
+    block: i64,
+}
+
+impl Disk {
+    pub fn new(words: Words) -> Self {
+        Self { words, block: 0 }
+    }
+
+    fn range(&self) -> io::Result<std::ops::Range<usize>> {
+        let size = self.block_size() as i64;
+        let start = self.block * size;
+        if self.block < 0 || start + size > self.words.borrow().len() as i64 {
+            return Err(invalid_input(format!("disk has no block {}", self.block)));
+        }
+        Ok(start as usize..(start + size) as usize)
+    }
+}
+
+impl Device for Disk {
+    fn block_size(&self) -> usize {
+        100
+    }
+
+    fn position(&mut self, x: Word) {
+        self.block = x.value();
+    }
+
+    fn input(&mut self, block: &mut [Word]) -> io::Result<()> {
+        let range = self.range()?;
+        block.copy_from_slice(&self.words.borrow()[range]);
+        Ok(())
+    }
+
+    fn output(&mut self, block: &[Word]) -> io::Result<()> {
+        let range = self.range()?;
+        self.words.borrow_mut()[range].copy_from_slice(block);
+        Ok(())
+    }
+
+    /// Positions the disk at the block in rX, there is nothing to wait for in memory.
+    fn control(&mut self, m: i16) -> io::Result<()> {
+        match m {
+            0 => self.range().map(|_| ()),
+            _ => Err(invalid_input(format!(
+                "disk can only be positioned, got IOC {}",
+                m
+            ))),
+        }
+    }
+}
+
+/// Card reader, reads blocks of 16 words, one line of 80 characters per card.
+pub struct CardReader<R> {
+    input: R,
+}
+
+impl<R: BufRead> CardReader<R> {
+    pub fn new(input: R) -> Self {
+        Self { input }
+    }
+}
+
+impl<R: BufRead> Device for CardReader<R> {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn input(&mut self, block: &mut [Word]) -> io::Result<()> {
+        read_text(&mut self.input, block)
+    }
+}
+
+/// Card punch, punches blocks of 16 words as lines of 80 characters.
+pub struct CardPunch<W> {
+    out: W,
+}
+
+impl<W: Write> CardPunch<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> Device for CardPunch<W> {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn output(&mut self, block: &[Word]) -> io::Result<()> {
+        writeln!(self.out, "{}", text(block).trim_end())?;
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use std::io::Cursor;
+
+    fn line(s: &str) -> Vec<Word> {
+        let mut bytes = s
+            .chars()
+            .map(|c| Byte::from_char(c).unwrap())
+            .collect::<Vec<_>>();
+        bytes.resize(120, Byte::default());
+        bytes
+            .chunks(5)
+            .map(|b| Word::new(Default::default(), b[0].0, b[1].0, b[2].0, b[3].0, b[4].0))
+            .collect()
+    }
+
+    #[test]
+    fn prints_lines_without_trailing_spaces() {
+        let mut printer = LinePrinter::new(vec![], 60, Paging::FormFeed);
+
+        printer.output(&line("FIRST FIVE HUNDRED PRIMES")).unwrap();
+        printer.output(&line("     0002 0233 0547")).unwrap();
+
+        assert_eq!(
+            String::from_utf8(printer.out).unwrap(),
+            "FIRST FIVE HUNDRED PRIMES\n     0002 0233 0547\n"
+        );
+    }
+
+    #[test]
+    fn prints_full_width_lines() {
+        let mut printer = LinePrinter::new(vec![], 60, Paging::FormFeed);
+        let text = "0123456789".repeat(12);
+
+        printer.output(&line(&text)).unwrap();
+
+        assert_eq!(String::from_utf8(printer.out).unwrap(), text + "\n");
+    }
+
+    #[test]
+    fn skips_to_new_page_with_form_feed() {
+        let mut printer = LinePrinter::new(vec![], 60, Paging::FormFeed);
+
+        printer.control(0).unwrap();
+        printer.output(&line("A")).unwrap();
+        printer.control(0).unwrap();
+        printer.output(&line("B")).unwrap();
+
+        assert_eq!(String::from_utf8(printer.out).unwrap(), "A\n\x0cB\n");
+    }
+
+    #[test]
+    fn skips_to_new_page_with_separator() {
+        let mut printer = LinePrinter::new(vec![], 60, Paging::Separator);
+
+        printer.output(&line("A")).unwrap();
+        printer.control(0).unwrap();
+        printer.control(0).unwrap();
+        printer.output(&line("B")).unwrap();
+
+        let separator = "-".repeat(120);
+        assert_eq!(
+            String::from_utf8(printer.out).unwrap(),
+            format!("A\n{}\nB\n", separator)
+        );
+    }
+
+    #[test]
+    fn starts_new_page_when_page_is_full() {
+        let mut printer = LinePrinter::new(vec![], 2, Paging::FormFeed);
+
+        for text in &["A", "B", "C", "D", "E"] {
+            printer.output(&line(text)).unwrap();
+        }
+
+        assert_eq!(
+            String::from_utf8(printer.out).unwrap(),
+            "A\nB\n\x0cC\nD\n\x0cE\n"
+        );
+    }
+
+    #[test]
+    fn rejects_other_control_operations() {
+        let mut printer = LinePrinter::new(vec![], 60, Paging::FormFeed);
+
+        let error = printer.control(1).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn transfers_blocks_of_24_words() {
+        let printer = LinePrinter::new(vec![], 60, Paging::FormFeed);
+
+        assert_eq!(printer.block_size(), 24);
+    }
+
+    fn read(device: &mut dyn Device) -> io::Result<String> {
+        let mut block = vec![Word::default(); device.block_size()];
+        device.input(&mut block)?;
+        Ok(text(&block))
+    }
+
+    #[test]
+    fn typewriter_reads_lines() {
+        let input = Cursor::new("Add 12\r\n\n+\n".to_string());
+        let mut typewriter = Typewriter::new(input, vec![]);
+
+        assert_eq!(read(&mut typewriter).unwrap(), format!("{:70}", "ADD 12"));
+        assert_eq!(read(&mut typewriter).unwrap(), " ".repeat(70));
+        assert_eq!(read(&mut typewriter).unwrap(), format!("{:70}", "+"));
+        let error = read(&mut typewriter).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn typewriter_rejects_invalid_lines() {
+        let input = Cursor::new(format!("{}\n#\n", "X".repeat(71)));
+        let mut typewriter = Typewriter::new(input, vec![]);
+
+        let error = read(&mut typewriter).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = read(&mut typewriter).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn typewriter_prints_lines() {
+        let mut typewriter = Typewriter::new(Cursor::new(String::new()), vec![]);
+        let mut block = line("1. ADD  2. QUIT");
+        block.truncate(14);
+
+        typewriter.output(&block).unwrap();
+
+        assert_eq!(
+            String::from_utf8(typewriter.out).unwrap(),
+            "1. ADD  2. QUIT\n"
+        );
+    }
+
+    #[test]
+    fn paper_tape_rewinds() {
+        let mut tape = PaperTape::new(Cursor::new("FIRST\nSECOND\n".to_string()));
+
+        assert_eq!(read(&mut tape).unwrap().trim_end(), "FIRST");
+        assert_eq!(read(&mut tape).unwrap().trim_end(), "SECOND");
+        tape.control(0).unwrap();
+        assert_eq!(read(&mut tape).unwrap().trim_end(), "FIRST");
+        assert_eq!(
+            tape.control(1).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    fn words(values: &[u8]) -> Words {
+        let words = values
+            .iter()
+            .map(|&v| Word::new(Default::default(), 0, 0, 0, 0, v))
+            .collect();
+        Rc::new(RefCell::new(words))
+    }
+
+    fn block(value: u8) -> Vec<Word> {
+        vec![Word::new(Default::default(), 0, 0, 0, 0, value); 100]
+    }
+
+    #[test]
+    fn tape_reads_and_writes_blocks() {
+        let storage = words(&[]);
+        let mut tape = Tape::new(storage.clone());
+
+        tape.output(&block(1)).unwrap();
+        tape.output(&block(2)).unwrap();
+        tape.output(&block(3)).unwrap();
+        tape.control(-2).unwrap();
+        tape.output(&block(4)).unwrap();
+        tape.control(0).unwrap();
+
+        let mut read = vec![Word::default(); 100];
+        tape.input(&mut read).unwrap();
+        assert_eq!(read, block(1));
+        tape.input(&mut read).unwrap();
+        assert_eq!(read, block(4));
+        let error = tape.input(&mut read).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(storage.borrow().len(), 200, "rest of the tape is erased");
+    }
+
+    #[test]
+    fn tape_skips_blocks() {
+        let storage = words(&[0; 300]);
+        storage.borrow_mut()[200] = Word::new(Default::default(), 0, 0, 0, 0, 7);
+        let mut tape = Tape::new(storage);
+
+        tape.control(-1).unwrap();
+        tape.control(2).unwrap();
+
+        let mut read = vec![Word::default(); 100];
+        tape.input(&mut read).unwrap();
+        assert_eq!(read[0], Word::new(Default::default(), 0, 0, 0, 0, 7));
+    }
+
+    #[test]
+    fn disk_uses_block_number_from_x() {
+        let storage = words(&[0; 300]);
+        let mut disk = Disk::new(storage.clone());
+
+        disk.position(Word::new(Default::default(), 0, 0, 0, 0, 2));
+        disk.output(&block(5)).unwrap();
+        disk.position(Word::new(Default::default(), 0, 0, 0, 0, 0));
+        disk.output(&block(6)).unwrap();
+
+        assert_eq!(storage.borrow()[..100], block(6)[..]);
+        assert_eq!(storage.borrow()[200..], block(5)[..]);
+        let mut read = vec![Word::default(); 100];
+        disk.position(Word::new(Default::default(), 0, 0, 0, 0, 2));
+        disk.input(&mut read).unwrap();
+        assert_eq!(read, block(5));
+        disk.position(Word::new(Default::default(), 0, 0, 0, 0, 3));
+        assert_eq!(
+            disk.input(&mut read).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            disk.control(0).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn card_reader_reads_deck() {
+        let mut reader = CardReader::new(Cursor::new("FIRST CARD\nsecond card\n"));
+
+        assert_eq!(read(&mut reader).unwrap(), format!("{:80}", "FIRST CARD"));
+        assert_eq!(read(&mut reader).unwrap(), format!("{:80}", "SECOND CARD"));
+        assert_eq!(
+            read(&mut reader).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn card_punch_punches_lines() {
+        let punched = Lines::default();
+        let mut punch = CardPunch::new(punched.clone());
+
+        punch.output(&line("CARD ONE")[..16]).unwrap();
+        punch.output(&line("CARD TWO")[..16]).unwrap();
+
+        assert_eq!(punched.lines(), vec!["CARD ONE", "CARD TWO"]);
+    }
+}
@@ -0,0 +1,140 @@
+//! Macro that writes MIX programs inside Rust code in the syntax of MIXAL, and the helpers
+//! that the code it expands to calls.
+
+use crate::{Address, IndexNumber, Instruction, Modification, Operation, Sign, Word};
+
+pub(crate) use mix_dsl::mix;
+
+/// Location in memory of the location counter.
+pub fn location(value: i64) -> usize {
+    value as usize
+}
+
+/// Word of the value of `CON`.
+pub fn word(value: i64) -> Word {
+    let sign = if value < 0 { Sign::Minus } else { Sign::Plus };
+    Word::from_magnitude(sign, value.unsigned_abs())
+}
+
+/// Word of the instruction with the address counted in the location counter.
+pub fn instruction(
+    operation: Operation,
+    address: i64,
+    index: Option<IndexNumber>,
+    field: Option<Modification>,
+) -> Word {
+    Word::from(Instruction::new(
+        operation,
+        Address::new(address as i16),
+        index,
+        field,
+    ))
+}
+
+#[cfg(test)]
+mod spec {
+    use super::mix;
+    use crate::mixal::assemble;
+    use crate::Mix;
+
+    #[test]
+    fn expands_to_the_image_of_the_assembled_source() {
+        let image = mix! {
+            N:      EQU  5;
+            BUF:    EQU  -2000;
+                    ORIG 3000;
+            START:  ENT1 N;
+            LOOP:   LDA  BUF,1(1:5);
+                    STA  -2000,1;
+                    JMP  (*+1);
+                    DEC1 1;
+                    J1P  LOOP;
+                    JMP  DONE;
+                    IN   1000(16);
+                    JBUS *(16);
+                    NOP  (0:2);
+            DONE:   HLT;
+            TABLE:  CON  -7;
+                    CON  (TABLE+N);
+                    END  START;
+        };
+
+        let program = assemble(
+            "N          EQU  5
+BUF        EQU  -2000
+           ORIG 3000
+START      ENT1 N
+LOOP       LDA  BUF,1(1:5)
+           STA  -2000,1
+           JMP  *+1
+           DEC1 1
+           J1P  LOOP
+           JMP  DONE
+           IN   1000(16)
+           JBUS *(16)
+           NOP  0(0:2)
+DONE       HLT
+TABLE      CON  -7
+           CON  TABLE+N
+           END  START",
+        )
+        .unwrap();
+        assert_eq!(image, program.image);
+    }
+
+    #[test]
+    fn programs_run_on_the_machine() {
+        let image = mix! {
+                   ORIG 100;
+            START: ENT1 0;
+                   ENTA 0;
+            LOOP:  ADD  DATA,1;
+                   INC1 1;
+                   CMP1 SIZE;
+                   JL   LOOP;
+                   STA  SUM;
+                   HLT;
+            SIZE:  CON  4;
+            DATA:  CON  10;
+                   CON  20;
+                   CON  -5;
+                   CON  100;
+            SUM:   CON  0;
+                   END  START;
+        };
+
+        let mut mix = Mix::default();
+        mix.load_image(&image);
+        let mix = mix.run();
+
+        assert!(mix.halted);
+        assert_eq!(mix.a.value(), 125);
+        assert_eq!(mix.memory[113], mix.a);
+    }
+
+    #[test]
+    fn expands_long_programs() {
+        let image = mix! {
+                   ORIG 1000;
+            START: NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP;
+                   NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP;
+                   NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP;
+                   NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP;
+                   NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP;
+                   NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP;
+                   NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP;
+                   NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP;
+                   NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP;
+                   NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP; NOP;
+            LAST:  JMP  START;
+                   END  START;
+        };
+
+        assert_eq!(image.words.len(), 101);
+        assert_eq!(image.start, 1000);
+        assert_eq!(
+            image.words[&1100],
+            super::instruction(crate::Operation::JMP, 1000, None, None)
+        );
+    }
+}
@@ -0,0 +1,486 @@
+//! Language server for MIXAL that speaks JSON-RPC over standard input and output.
+//!
+//! It publishes the errors of the assembler, finds definitions and references of symbols
+//! and local symbols, describes operations on hover and completes mnemonics and symbols.
+
+mod json;
+
+use crate::mixal::assemble;
+use crate::mixal::navigation::{
+    definition, occurrence_at, occurrences, operation_at, references_to,
+};
+use crate::{Byte, Modification, Operation, OPERATIONS};
+use json::Json;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+/// Pseudo-operations offered by completion next to the mnemonics.
+const PSEUDO_OPERATIONS: [&str; 5] = ["EQU", "ORIG", "CON", "ALF", "END"];
+
+/// Serves messages until the client asks to exit, returns the exit code:
+/// 0 after a shutdown request and 1 otherwise.
+pub fn serve(input: &mut dyn BufRead, out: &mut dyn Write) -> i32 {
+    let mut server = Server::default();
+    loop {
+        let result = match read_message(input) {
+            Ok(Some(text)) => match Json::parse(&text) {
+                Ok(message) => server.handle(&message, out),
+                Err(error) => {
+                    let error = response_error(Json::Null, -32700, error);
+                    write_message(out, &error).map(|_| None)
+                }
+            },
+            Ok(None) => return 1,
+            Err(_) => return 1,
+        };
+        match result {
+            Ok(Some(code)) => return code,
+            Ok(None) => {}
+            Err(_) => return 1,
+        }
+    }
+}
+
+/// Body of the next message, `None` at the end of the input.
+fn read_message(input: &mut dyn BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message(out: &mut dyn Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(out, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    out.flush()
+}
+
+fn response(id: Json, result: Json) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::from("2.0")),
+        ("id", id),
+        ("result", result),
+    ])
+}
+
+fn response_error(id: Json, code: i32, message: String) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::from("2.0")),
+        ("id", id),
+        (
+            "error",
+            Json::object(vec![
+                ("code", Json::Number(code as f64)),
+                ("message", Json::from(message)),
+            ]),
+        ),
+    ])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::from("2.0")),
+        ("method", Json::from(method)),
+        ("params", params),
+    ])
+}
+
+fn range(line: usize, start: usize, end: usize) -> Json {
+    let position = |character: usize| {
+        Json::object(vec![
+            ("line", Json::from(line)),
+            ("character", Json::from(character)),
+        ])
+    };
+    Json::object(vec![("start", position(start)), ("end", position(end))])
+}
+
+#[derive(Default)]
+struct Server {
+    /// Text of the open documents by their URIs.
+    documents: BTreeMap<String, String>,
+    shutdown: bool,
+}
+
+impl Server {
+    /// Answers the message, returns the exit code once the client asks to exit.
+    fn handle(&mut self, message: &Json, out: &mut dyn Write) -> io::Result<Option<i32>> {
+        let id = message.get("id").clone();
+        let params = message.get("params");
+        let uri = params
+            .get("textDocument")
+            .get("uri")
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let result = match message.get("method").as_str().unwrap_or_default() {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "exit" => return Ok(Some(if self.shutdown { 0 } else { 1 })),
+            "textDocument/didOpen" => {
+                let text = params.get("textDocument").get("text");
+                self.change(out, uri, text.as_str().unwrap_or_default())?;
+                return Ok(None);
+            }
+            "textDocument/didChange" => {
+                let changes = params.get("contentChanges").as_array().unwrap_or_default();
+                if let Some(text) = changes.last().and_then(|c| c.get("text").as_str()) {
+                    self.change(out, uri, text)?;
+                }
+                return Ok(None);
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                publish(out, &uri, vec![])?;
+                return Ok(None);
+            }
+            "textDocument/definition" => Ok(self.definition(&uri, params)),
+            "textDocument/references" => Ok(self.references(&uri, params)),
+            "textDocument/hover" => Ok(self.hover(&uri, params)),
+            "textDocument/completion" => Ok(self.completion(&uri)),
+            _ if id == Json::Null => return Ok(None),
+            method => Err(format!("unknown method {}", method)),
+        };
+        if id == Json::Null {
+            return Ok(None);
+        }
+        let message = match result {
+            Ok(result) => response(id, result),
+            Err(message) => response_error(id, -32601, message),
+        };
+        write_message(out, &message).map(|_| None)
+    }
+
+    fn change(&mut self, out: &mut dyn Write, uri: String, text: &str) -> io::Result<()> {
+        publish(out, &uri, diagnostics(text))?;
+        self.documents.insert(uri, text.to_string());
+        Ok(())
+    }
+
+    fn document(&self, uri: &str) -> &str {
+        self.documents.get(uri).map_or("", String::as_str)
+    }
+
+    fn definition(&self, uri: &str, params: &Json) -> Json {
+        let occurrences = occurrences(self.document(uri));
+        let (line, character) = position(params);
+        occurrence_at(&occurrences, line, character)
+            .and_then(|occurrence| definition(&occurrences, occurrence))
+            .map_or(Json::Null, |d| location(uri, d.line, d.start, d.end))
+    }
+
+    fn references(&self, uri: &str, params: &Json) -> Json {
+        let occurrences = occurrences(self.document(uri));
+        let (line, character) = position(params);
+        let declaration = params
+            .get("context")
+            .get("includeDeclaration")
+            .as_bool()
+            .unwrap_or(false);
+        let references = match occurrence_at(&occurrences, line, character) {
+            Some(occurrence) => references_to(&occurrences, occurrence, declaration),
+            None => vec![],
+        };
+        Json::from(
+            references
+                .into_iter()
+                .map(|r| location(uri, r.line, r.start, r.end))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn hover(&self, uri: &str, params: &Json) -> Json {
+        let (line, character) = position(params);
+        match operation_at(self.document(uri), line, character) {
+            Some(operation) => Json::object(vec![(
+                "contents",
+                Json::object(vec![
+                    ("kind", Json::from("markdown")),
+                    ("value", Json::from(describe(operation))),
+                ]),
+            )]),
+            None => Json::Null,
+        }
+    }
+
+    fn completion(&self, uri: &str) -> Json {
+        let text = self.document(uri);
+        let values = assemble(text).map(|program| program.symbols).ok();
+        let item = |label: String, kind: usize, detail: String| {
+            Json::object(vec![
+                ("label", Json::from(label)),
+                ("kind", Json::from(kind)),
+                ("detail", Json::from(detail)),
+            ])
+        };
+        let mut items: Vec<Json> = OPERATIONS
+            .iter()
+            .map(|(operation, _, _, _)| item(operation.mnemonic(), 14, operation.description()))
+            .collect();
+        items.extend(
+            PSEUDO_OPERATIONS
+                .iter()
+                .map(|name| item(name.to_string(), 14, "pseudo-operation".to_string())),
+        );
+        let mut symbols: Vec<String> = occurrences(text)
+            .into_iter()
+            .filter(|o| o.definition && !o.is_local())
+            .map(|o| o.name)
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        items.extend(symbols.into_iter().map(|symbol| {
+            let detail = values
+                .as_ref()
+                .and_then(|values| values.get(&symbol))
+                .map_or("symbol".to_string(), |value| format!("symbol = {}", value));
+            item(symbol, 6, detail)
+        }));
+        Json::from(items)
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                ("textDocumentSync", Json::from(1)),
+                ("definitionProvider", Json::from(true)),
+                ("referencesProvider", Json::from(true)),
+                ("hoverProvider", Json::from(true)),
+                ("completionProvider", Json::object(vec![])),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object(vec![("name", Json::from("mix-rs"))]),
+        ),
+    ])
+}
+
+fn position(params: &Json) -> (usize, usize) {
+    let position = params.get("position");
+    (
+        position.get("line").as_usize().unwrap_or_default(),
+        position.get("character").as_usize().unwrap_or_default(),
+    )
+}
+
+fn location(uri: &str, line: usize, start: usize, end: usize) -> Json {
+    Json::object(vec![
+        ("uri", Json::from(uri)),
+        ("range", range(line, start, end)),
+    ])
+}
+
+fn publish(out: &mut dyn Write, uri: &str, diagnostics: Vec<Json>) -> io::Result<()> {
+    let params = Json::object(vec![
+        ("uri", Json::from(uri)),
+        ("diagnostics", Json::from(diagnostics)),
+    ]);
+    write_message(
+        out,
+        &notification("textDocument/publishDiagnostics", params),
+    )
+}
+
+/// Errors of the assembler, each one covers its whole line.
+fn diagnostics(text: &str) -> Vec<Json> {
+    let errors = match assemble(text) {
+        Ok(_) => return vec![],
+        Err(errors) => errors,
+    };
+    errors
+        .into_iter()
+        .map(|error| {
+            let line = error.line.saturating_sub(1);
+            let length = text.lines().nth(line).map_or(0, |l| l.chars().count());
+            Json::object(vec![
+                ("range", range(line, 0, length)),
+                ("severity", Json::from(1)),
+                ("source", Json::from("mixal")),
+                ("message", Json::from(error.message)),
+            ])
+        })
+        .collect()
+}
+
+/// Meaning, code, default field and time of the operation.
+fn describe(operation: Operation) -> String {
+    let field = operation.default_modification();
+    let field = match field {
+        Modification::Field { l, r } if operation.uses_field() => format!("({}:{})", l, r),
+        _ => Byte::from(field).0.to_string(),
+    };
+    let time = match operation {
+        Operation::MOVE => format!("{} + 2F", operation.time()),
+        _ => operation.time().to_string(),
+    };
+    format!(
+        "**{}** — {}\n\nC = {}, default F = {}, time {}u",
+        operation.mnemonic(),
+        operation.description(),
+        operation.code().0,
+        field,
+        time
+    )
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+
+    fn frame(message: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", message.len(), message)
+    }
+
+    fn session(messages: &[String]) -> (i32, Vec<Json>) {
+        let input: String = messages.iter().map(|m| frame(m)).collect();
+        let mut out = vec![];
+        let code = serve(&mut input.as_bytes(), &mut out);
+        let mut output = &out[..];
+        let mut replies = vec![];
+        while let Some(text) = read_message(&mut output).unwrap() {
+            replies.push(Json::parse(&text).unwrap());
+        }
+        (code, replies)
+    }
+
+    fn request(id: usize, method: &str, params: &str) -> String {
+        format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{}}}"#,
+            id, method, params
+        )
+    }
+
+    fn at(line: usize, character: usize) -> String {
+        format!(
+            r#"{{"textDocument":{{"uri":"file:///p.mixal"}},"position":{{"line":{},"character":{}}},"context":{{"includeDeclaration":true}}}}"#,
+            line, character
+        )
+    }
+
+    const OPEN: &str = r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///p.mixal","languageId":"mixal","version":1,"text":"START      LDA  BUF\n2H         NOP\n           JMP  2B\n           JMP  MISSING\nBUF        CON  0\n           END  START\n"}}}"#;
+
+    #[test]
+    fn answers_requests_about_an_open_document() {
+        let (code, replies) = session(&[
+            request(1, "initialize", "{}"),
+            r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#.to_string(),
+            OPEN.to_string(),
+            request(2, "textDocument/definition", &at(0, 17)),
+            request(3, "textDocument/references", &at(1, 0)),
+            request(4, "textDocument/hover", &at(0, 12)),
+            request(5, "textDocument/completion", &at(0, 0)),
+            request(6, "shutdown", "null"),
+            r#"{"jsonrpc":"2.0","method":"exit"}"#.to_string(),
+        ]);
+
+        assert_eq!(code, 0);
+        assert_eq!(
+            replies[0]
+                .get("result")
+                .get("capabilities")
+                .get("hoverProvider"),
+            &Json::Bool(true)
+        );
+        assert_eq!(
+            replies[1].to_string(),
+            r#"{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{"uri":"file:///p.mixal","diagnostics":[{"range":{"start":{"line":3,"character":0},"end":{"line":3,"character":23}},"severity":1,"source":"mixal","message":"undefined symbol MISSING"}]}}"#
+        );
+        assert_eq!(
+            replies[2].get("result").to_string(),
+            r#"{"uri":"file:///p.mixal","range":{"start":{"line":4,"character":0},"end":{"line":4,"character":3}}}"#
+        );
+        let lines = |reply: &Json| {
+            reply
+                .get("result")
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|l| {
+                    let start = l.get("range").get("start");
+                    (
+                        start.get("line").as_usize(),
+                        start.get("character").as_usize(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lines(&replies[3]),
+            vec![(Some(1), Some(0)), (Some(2), Some(16))]
+        );
+        assert_eq!(
+            replies[4]
+                .get("result")
+                .get("contents")
+                .get("value")
+                .as_str(),
+            Some("**LDA** — load M into rA\n\nC = 8, default F = (0:5), time 2u")
+        );
+        let completions = replies[5].get("result").as_array().unwrap();
+        let labels: Vec<_> = completions
+            .iter()
+            .map(|c| c.get("label").as_str().unwrap())
+            .collect();
+        assert!(labels.contains(&"LDA") && labels.contains(&"EQU"));
+        assert_eq!(labels[labels.len() - 2..], ["BUF", "START"]);
+        assert_eq!(replies[6].get("result"), &Json::Null);
+        assert_eq!(replies.len(), 7);
+    }
+
+    #[test]
+    fn reports_unknown_methods_and_invalid_messages() {
+        let (code, replies) = session(&[
+            request(1, "workspace/symbol", "{}"),
+            "{invalid".to_string(),
+            r#"{"jsonrpc":"2.0","method":"$/cancelRequest","params":{}}"#.to_string(),
+            r#"{"jsonrpc":"2.0","method":"exit"}"#.to_string(),
+        ]);
+
+        assert_eq!(code, 1);
+        assert_eq!(
+            replies[0].to_string(),
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"unknown method workspace/symbol"}}"#
+        );
+        assert_eq!(replies[1].get("error").get("code"), &Json::Number(-32700.0));
+        assert_eq!(replies.len(), 2);
+    }
+
+    #[test]
+    fn describes_operations() {
+        assert_eq!(
+            describe(Operation::MOVE),
+            "**MOVE** — move F words from M to the location in rI1\n\nC = 7, default F = 1, time 1 + 2Fu"
+        );
+        assert_eq!(
+            describe(Operation::J3NZ),
+            "**J3NZ** — jump to M if rI3 is nonzero\n\nC = 43, default F = 4, time 1u"
+        );
+    }
+}
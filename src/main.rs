@@ -1,5 +1,29 @@
+// Nothing outside the specs builds programs with it while the crate is only a binary.
+#[cfg(test)]
+mod builder;
+mod cli;
+mod debugger;
+mod device;
+#[cfg(test)]
+mod dsl;
+mod lsp;
+mod mixal;
+
+use device::Device;
+use std::collections::BTreeMap;
+use std::io;
+
 const BYTE: u8 = 64;
 const WORD_BYTES: u8 = 5;
+const MEMORY_SIZE: usize = 4000;
+const UNITS: usize = 21;
+
+/// MIX character set, the code of a character is its index.
+const CHARACTERS: [char; 56] = [
+    ' ', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'Δ', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q',
+    'R', 'Σ', 'Π', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '0', '1', '2', '3', '4', '5', '6', '7',
+    '8', '9', '.', ',', '(', ')', '+', '-', '*', '/', '=', '$', '<', '>', '@', ';', ':', '\'',
+];
 
 #[derive(Debug, PartialEq, PartialOrd, Clone, Copy, Default)]
 struct Byte(pub u8);
@@ -8,18 +32,25 @@ impl Byte {
         debug_assert!(b < BYTE, "Byte value should be smaller than {}", BYTE);
         Byte(b)
     }
+
+    fn from_char(c: char) -> Option<Self> {
+        CHARACTERS
+            .iter()
+            .position(|&m| m == c)
+            .map(|code| Byte::new(code as u8))
+    }
+
+    fn char(self) -> char {
+        CHARACTERS.get(self.0 as usize).copied().unwrap_or('?')
+    }
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy, Default)]
 enum Sign {
     Minus,
+    #[default]
     Plus,
 }
-impl Default for Sign {
-    fn default() -> Self {
-        Sign::Plus
-    }
-}
 impl Sign {
     fn opposite(&self) -> Self {
         match self {
@@ -49,6 +80,33 @@ impl Word {
         }
     }
 
+    fn from_magnitude(sign: Sign, magnitude: u64) -> Self {
+        let mut word = Word {
+            sign,
+            ..Word::default()
+        };
+        let mut rest = magnitude;
+        for byte in word.bytes.iter_mut().rev() {
+            *byte = Byte::new((rest % BYTE as u64) as u8);
+            rest /= BYTE as u64;
+        }
+        debug_assert!(rest == 0, "{} does not fit into a word", magnitude);
+        word
+    }
+
+    fn magnitude(self) -> u64 {
+        self.bytes
+            .iter()
+            .fold(0, |acc, b| acc * BYTE as u64 + b.0 as u64)
+    }
+
+    fn value(self) -> i64 {
+        match self.sign {
+            Sign::Plus => self.magnitude() as i64,
+            Sign::Minus => -(self.magnitude() as i64),
+        }
+    }
+
     fn slice(self, m: Modification) -> Self {
         match m {
             Modification::Field { l, r } => {
@@ -115,7 +173,7 @@ impl Word {
                 } else {
                     borrow = 0;
                 }
-                a.bytes[i] = Byte::new(s.abs() as u8 % BYTE);
+                a.bytes[i] = Byte::new(s.unsigned_abs() as u8 % BYTE);
             }
             (a, borrow > 0)
         }
@@ -185,6 +243,7 @@ struct Index {
     sign: Sign,
     bytes: [Byte; 2],
 }
+#[cfg(test)]
 impl Index {
     fn new(sign: Sign, b0: u8, b1: u8) -> Self {
         Self {
@@ -201,16 +260,16 @@ impl From<Word> for Index {
         }
     }
 }
-impl Into<Word> for Index {
-    fn into(self) -> Word {
+impl From<Index> for Word {
+    fn from(index: Index) -> Self {
         Word {
-            sign: self.sign,
+            sign: index.sign,
             bytes: [
                 Byte::default(),
                 Byte::default(),
                 Byte::default(),
-                self.bytes[0],
-                self.bytes[1],
+                index.bytes[0],
+                index.bytes[1],
             ],
         }
     }
@@ -220,6 +279,7 @@ impl Into<Word> for Index {
 struct Jump {
     bytes: [Byte; 2],
 }
+#[cfg(test)]
 impl Jump {
     fn new(b0: u8, b1: u8) -> Self {
         Self {
@@ -234,31 +294,27 @@ impl From<Word> for Jump {
         }
     }
 }
-impl Into<Word> for Jump {
-    fn into(self) -> Word {
+impl From<Jump> for Word {
+    fn from(jump: Jump) -> Self {
         Word {
             sign: Sign::Plus,
             bytes: [
                 Byte::default(),
                 Byte::default(),
                 Byte::default(),
-                self.bytes[0],
-                self.bytes[1],
+                jump.bytes[0],
+                jump.bytes[1],
             ],
         }
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
 enum Toggle {
     On,
+    #[default]
     Off,
 }
-impl Default for Toggle {
-    fn default() -> Self {
-        Toggle::Off
-    }
-}
 impl From<bool> for Toggle {
     fn from(b: bool) -> Self {
         if b {
@@ -268,16 +324,13 @@ impl From<bool> for Toggle {
         }
     }
 }
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
 enum Comparison {
     Less,
+    #[default]
     Equal,
     Greater,
 }
-impl Default for Comparison {
-    fn default() -> Self {
-        Comparison::Equal
-    }
-}
 
 struct Mix {
     a: Word,
@@ -291,7 +344,15 @@ struct Mix {
     j: Jump,
     overflow: Toggle,
     comparison_indicator: Comparison,
-    memory: [Word; 4000],
+    memory: [Word; MEMORY_SIZE],
+    program_counter: usize,
+    halted: bool,
+    clock: u64,
+    devices: [Option<Unit>; UNITS],
+    fault: Option<Fault>,
+    watchpoints: Vec<Watchpoint>,
+    /// Watchpoints that the last instruction fired.
+    changes: Vec<Change>,
 }
 
 impl Default for Mix {
@@ -308,11 +369,94 @@ impl Default for Mix {
             j: Default::default(),
             overflow: Default::default(),
             comparison_indicator: Default::default(),
-            memory: [Default::default(); 4000],
+            memory: [Default::default(); MEMORY_SIZE],
+            program_counter: 0,
+            halted: false,
+            clock: 0,
+            devices: Default::default(),
+            fault: None,
+            watchpoints: vec![],
+            changes: vec![],
+        }
+    }
+}
+
+/// Contents of memory locations and the location where the program starts.
+#[derive(Debug, PartialEq, Default)]
+struct Image {
+    words: BTreeMap<usize, Word>,
+    start: usize,
+}
+
+/// Device attached to an input-output unit, with the timing of its operations.
+struct Unit {
+    device: Box<dyn Device>,
+    /// Time to transfer one block, in units of u.
+    transfer_time: u64,
+    /// Clock time when the current operation of the unit completes.
+    busy_until: u64,
+}
+
+impl Unit {
+    /// Waits for the current operation and starts the next one, returns the clock when it starts.
+    fn start(&mut self, clock: u64) -> u64 {
+        let start = clock.max(self.busy_until);
+        self.busy_until = start + self.transfer_time;
+        start
+    }
+}
+
+#[derive(Debug)]
+enum Fault {
+    /// Input-output instruction refers to a unit without a device.
+    NoDevice(u8),
+    /// Effective address is outside of memory or does not fit into two bytes.
+    InvalidAddress(i16),
+    /// Word at the location does not encode an instruction.
+    InvalidInstruction(usize),
+    /// Device failed to perform the operation.
+    Device(u8, io::Error),
+}
+
+/// Register of the machine, as operations and watchpoints name it.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Register {
+    A,
+    X,
+    I(IndexNumber),
+    J,
+}
+impl Register {
+    /// Register of the operations with codes 40–63, which come in groups of eight
+    /// for A, I1–I6 and X.
+    fn of_code(code: Byte) -> Self {
+        match IndexNumber::decode(Byte::new(code.0 % 8)) {
+            Some(Some(index)) => Register::I(index),
+            Some(None) => Register::A,
+            None => Register::X,
         }
     }
 }
 
+/// Condition on memory or a register that the machine watches while it executes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Watchpoint {
+    /// Every write to the location, even one that leaves the word as it was.
+    Write(usize),
+    /// Write that changes the field of the word at the location.
+    Field(usize, Modification),
+    /// Register that becomes equal to the value.
+    Register(Register, i64),
+}
+
+/// Watchpoint that fired, with the watched value before and after the instruction.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Change {
+    watchpoint: Watchpoint,
+    old: Word,
+    new: Word,
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 enum Modification {
     Field { l: u8, r: u8 },
@@ -324,9 +468,9 @@ impl From<Byte> for Modification {
         Modification::field(l, r)
     }
 }
-impl Into<Byte> for Modification {
-    fn into(self) -> Byte {
-        match self {
+impl From<Modification> for Byte {
+    fn from(m: Modification) -> Self {
+        match m {
             Modification::Field { l, r } => Byte::new(l * 8 + r),
         }
     }
@@ -335,16 +479,38 @@ impl Modification {
     fn field(l: u8, r: u8) -> Self {
         Modification::Field { l, r }
     }
+
+    fn is_valid(self) -> bool {
+        match self {
+            Modification::Field { l, r } => l <= r && r <= WORD_BYTES,
+        }
+    }
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum IndexNumber {
-    I1,
+    I1 = 1,
     I2,
     I3,
     I4,
     I5,
     I6,
 }
+impl IndexNumber {
+    fn decode(byte: Byte) -> Option<Option<Self>> {
+        match byte.0 {
+            0 => Some(None),
+            1 => Some(Some(IndexNumber::I1)),
+            2 => Some(Some(IndexNumber::I2)),
+            3 => Some(Some(IndexNumber::I3)),
+            4 => Some(Some(IndexNumber::I4)),
+            5 => Some(Some(IndexNumber::I5)),
+            6 => Some(Some(IndexNumber::I6)),
+            _ => None,
+        }
+    }
+}
+#[derive(Debug, PartialEq, Clone, Copy)]
 struct Address {
     sign: Sign,
     bytes: [Byte; 2],
@@ -367,46 +533,450 @@ impl Address {
     fn memory_index(&self) -> usize {
         self.bytes[0].0 as usize * BYTE as usize + self.bytes[1].0 as usize
     }
+
+    fn value(&self) -> i16 {
+        let value = self.memory_index() as i16;
+        match self.sign {
+            Sign::Plus => value,
+            Sign::Minus => -value,
+        }
+    }
+}
+impl From<Address> for Word {
+    fn from(address: Address) -> Self {
+        Word {
+            sign: address.sign,
+            bytes: [
+                Byte::default(),
+                Byte::default(),
+                Byte::default(),
+                address.bytes[0],
+                address.bytes[1],
+            ],
+        }
+    }
 }
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum Operation {
+    NOP,
+    ADD,
+    SUB,
+    MUL,
+    DIV,
+    NUM,
+    CHAR,
+    HLT,
+    SLA,
+    SRA,
+    SLAX,
+    SRAX,
+    SLC,
+    SRC,
+    MOVE,
     LDA,
-    LDX,
     LD1,
     LD2,
     LD3,
     LD4,
     LD5,
     LD6,
+    LDX,
     LDAN,
-    LDXN,
     LD1N,
     LD2N,
     LD3N,
     LD4N,
     LD5N,
     LD6N,
+    LDXN,
     STA,
-    STX,
     ST1,
     ST2,
     ST3,
     ST4,
     ST5,
     ST6,
+    STX,
     STJ,
     STZ,
-    ADD,
-    SUB,
-    MUL,
+    JBUS,
+    IOC,
+    IN,
+    OUT,
+    JRED,
+    JMP,
+    JSJ,
+    JOV,
+    JNOV,
+    JL,
+    JE,
+    JG,
+    JGE,
+    JNE,
+    JLE,
+    JAN,
+    JAZ,
+    JAP,
+    JANN,
+    JANZ,
+    JANP,
+    J1N,
+    J1Z,
+    J1P,
+    J1NN,
+    J1NZ,
+    J1NP,
+    J2N,
+    J2Z,
+    J2P,
+    J2NN,
+    J2NZ,
+    J2NP,
+    J3N,
+    J3Z,
+    J3P,
+    J3NN,
+    J3NZ,
+    J3NP,
+    J4N,
+    J4Z,
+    J4P,
+    J4NN,
+    J4NZ,
+    J4NP,
+    J5N,
+    J5Z,
+    J5P,
+    J5NN,
+    J5NZ,
+    J5NP,
+    J6N,
+    J6Z,
+    J6P,
+    J6NN,
+    J6NZ,
+    J6NP,
+    JXN,
+    JXZ,
+    JXP,
+    JXNN,
+    JXNZ,
+    JXNP,
+    INCA,
+    DECA,
+    ENTA,
+    ENNA,
+    INC1,
+    DEC1,
+    ENT1,
+    ENN1,
+    INC2,
+    DEC2,
+    ENT2,
+    ENN2,
+    INC3,
+    DEC3,
+    ENT3,
+    ENN3,
+    INC4,
+    DEC4,
+    ENT4,
+    ENN4,
+    INC5,
+    DEC5,
+    ENT5,
+    ENN5,
+    INC6,
+    DEC6,
+    ENT6,
+    ENN6,
+    INCX,
+    DECX,
+    ENTX,
+    ENNX,
+    CMPA,
+    CMP1,
+    CMP2,
+    CMP3,
+    CMP4,
+    CMP5,
+    CMP6,
+    CMPX,
 }
+
+/// Operation code, default field specification and execution time of every operation,
+/// in the same order as the variants of `Operation`.
+const OPERATIONS: [(Operation, u8, u8, u64); 144] = [
+    (Operation::NOP, 0, 0, 1),
+    (Operation::ADD, 1, 5, 2),
+    (Operation::SUB, 2, 5, 2),
+    (Operation::MUL, 3, 5, 10),
+    (Operation::DIV, 4, 5, 12),
+    (Operation::NUM, 5, 0, 10),
+    (Operation::CHAR, 5, 1, 10),
+    (Operation::HLT, 5, 2, 10),
+    (Operation::SLA, 6, 0, 2),
+    (Operation::SRA, 6, 1, 2),
+    (Operation::SLAX, 6, 2, 2),
+    (Operation::SRAX, 6, 3, 2),
+    (Operation::SLC, 6, 4, 2),
+    (Operation::SRC, 6, 5, 2),
+    (Operation::MOVE, 7, 1, 1),
+    (Operation::LDA, 8, 5, 2),
+    (Operation::LD1, 9, 5, 2),
+    (Operation::LD2, 10, 5, 2),
+    (Operation::LD3, 11, 5, 2),
+    (Operation::LD4, 12, 5, 2),
+    (Operation::LD5, 13, 5, 2),
+    (Operation::LD6, 14, 5, 2),
+    (Operation::LDX, 15, 5, 2),
+    (Operation::LDAN, 16, 5, 2),
+    (Operation::LD1N, 17, 5, 2),
+    (Operation::LD2N, 18, 5, 2),
+    (Operation::LD3N, 19, 5, 2),
+    (Operation::LD4N, 20, 5, 2),
+    (Operation::LD5N, 21, 5, 2),
+    (Operation::LD6N, 22, 5, 2),
+    (Operation::LDXN, 23, 5, 2),
+    (Operation::STA, 24, 5, 2),
+    (Operation::ST1, 25, 5, 2),
+    (Operation::ST2, 26, 5, 2),
+    (Operation::ST3, 27, 5, 2),
+    (Operation::ST4, 28, 5, 2),
+    (Operation::ST5, 29, 5, 2),
+    (Operation::ST6, 30, 5, 2),
+    (Operation::STX, 31, 5, 2),
+    (Operation::STJ, 32, 2, 2),
+    (Operation::STZ, 33, 5, 2),
+    (Operation::JBUS, 34, 0, 1),
+    (Operation::IOC, 35, 0, 1),
+    (Operation::IN, 36, 0, 1),
+    (Operation::OUT, 37, 0, 1),
+    (Operation::JRED, 38, 0, 1),
+    (Operation::JMP, 39, 0, 1),
+    (Operation::JSJ, 39, 1, 1),
+    (Operation::JOV, 39, 2, 1),
+    (Operation::JNOV, 39, 3, 1),
+    (Operation::JL, 39, 4, 1),
+    (Operation::JE, 39, 5, 1),
+    (Operation::JG, 39, 6, 1),
+    (Operation::JGE, 39, 7, 1),
+    (Operation::JNE, 39, 8, 1),
+    (Operation::JLE, 39, 9, 1),
+    (Operation::JAN, 40, 0, 1),
+    (Operation::JAZ, 40, 1, 1),
+    (Operation::JAP, 40, 2, 1),
+    (Operation::JANN, 40, 3, 1),
+    (Operation::JANZ, 40, 4, 1),
+    (Operation::JANP, 40, 5, 1),
+    (Operation::J1N, 41, 0, 1),
+    (Operation::J1Z, 41, 1, 1),
+    (Operation::J1P, 41, 2, 1),
+    (Operation::J1NN, 41, 3, 1),
+    (Operation::J1NZ, 41, 4, 1),
+    (Operation::J1NP, 41, 5, 1),
+    (Operation::J2N, 42, 0, 1),
+    (Operation::J2Z, 42, 1, 1),
+    (Operation::J2P, 42, 2, 1),
+    (Operation::J2NN, 42, 3, 1),
+    (Operation::J2NZ, 42, 4, 1),
+    (Operation::J2NP, 42, 5, 1),
+    (Operation::J3N, 43, 0, 1),
+    (Operation::J3Z, 43, 1, 1),
+    (Operation::J3P, 43, 2, 1),
+    (Operation::J3NN, 43, 3, 1),
+    (Operation::J3NZ, 43, 4, 1),
+    (Operation::J3NP, 43, 5, 1),
+    (Operation::J4N, 44, 0, 1),
+    (Operation::J4Z, 44, 1, 1),
+    (Operation::J4P, 44, 2, 1),
+    (Operation::J4NN, 44, 3, 1),
+    (Operation::J4NZ, 44, 4, 1),
+    (Operation::J4NP, 44, 5, 1),
+    (Operation::J5N, 45, 0, 1),
+    (Operation::J5Z, 45, 1, 1),
+    (Operation::J5P, 45, 2, 1),
+    (Operation::J5NN, 45, 3, 1),
+    (Operation::J5NZ, 45, 4, 1),
+    (Operation::J5NP, 45, 5, 1),
+    (Operation::J6N, 46, 0, 1),
+    (Operation::J6Z, 46, 1, 1),
+    (Operation::J6P, 46, 2, 1),
+    (Operation::J6NN, 46, 3, 1),
+    (Operation::J6NZ, 46, 4, 1),
+    (Operation::J6NP, 46, 5, 1),
+    (Operation::JXN, 47, 0, 1),
+    (Operation::JXZ, 47, 1, 1),
+    (Operation::JXP, 47, 2, 1),
+    (Operation::JXNN, 47, 3, 1),
+    (Operation::JXNZ, 47, 4, 1),
+    (Operation::JXNP, 47, 5, 1),
+    (Operation::INCA, 48, 0, 1),
+    (Operation::DECA, 48, 1, 1),
+    (Operation::ENTA, 48, 2, 1),
+    (Operation::ENNA, 48, 3, 1),
+    (Operation::INC1, 49, 0, 1),
+    (Operation::DEC1, 49, 1, 1),
+    (Operation::ENT1, 49, 2, 1),
+    (Operation::ENN1, 49, 3, 1),
+    (Operation::INC2, 50, 0, 1),
+    (Operation::DEC2, 50, 1, 1),
+    (Operation::ENT2, 50, 2, 1),
+    (Operation::ENN2, 50, 3, 1),
+    (Operation::INC3, 51, 0, 1),
+    (Operation::DEC3, 51, 1, 1),
+    (Operation::ENT3, 51, 2, 1),
+    (Operation::ENN3, 51, 3, 1),
+    (Operation::INC4, 52, 0, 1),
+    (Operation::DEC4, 52, 1, 1),
+    (Operation::ENT4, 52, 2, 1),
+    (Operation::ENN4, 52, 3, 1),
+    (Operation::INC5, 53, 0, 1),
+    (Operation::DEC5, 53, 1, 1),
+    (Operation::ENT5, 53, 2, 1),
+    (Operation::ENN5, 53, 3, 1),
+    (Operation::INC6, 54, 0, 1),
+    (Operation::DEC6, 54, 1, 1),
+    (Operation::ENT6, 54, 2, 1),
+    (Operation::ENN6, 54, 3, 1),
+    (Operation::INCX, 55, 0, 1),
+    (Operation::DECX, 55, 1, 1),
+    (Operation::ENTX, 55, 2, 1),
+    (Operation::ENNX, 55, 3, 1),
+    (Operation::CMPA, 56, 5, 2),
+    (Operation::CMP1, 57, 5, 2),
+    (Operation::CMP2, 58, 5, 2),
+    (Operation::CMP3, 59, 5, 2),
+    (Operation::CMP4, 60, 5, 2),
+    (Operation::CMP5, 61, 5, 2),
+    (Operation::CMP6, 62, 5, 2),
+    (Operation::CMPX, 63, 5, 2),
+];
+
 impl Operation {
+    fn code(self) -> Byte {
+        Byte::new(OPERATIONS[self as usize].1)
+    }
+
     fn default_modification(self) -> Modification {
-        match self {
-            Operation::STJ => Modification::field(0, 2),
-            _ => Modification::field(0, 5),
+        Modification::from(Byte::new(OPERATIONS[self as usize].2))
+    }
+
+    /// Execution time in units of u, not counting the time spent waiting for devices.
+    fn time(self) -> u64 {
+        OPERATIONS[self as usize].3
+    }
+
+    /// Symbolic name of the operation in MIXAL.
+    fn mnemonic(self) -> String {
+        format!("{:?}", self)
+    }
+
+    /// What the operation does, in a few words.
+    fn description(self) -> String {
+        let code = self.code().0;
+        let field = Byte::from(self.default_modification()).0 as usize;
+        let register = |first: u8| match code - first {
+            0 => "rA".to_string(),
+            7 => "rX".to_string(),
+            i => format!("rI{}", i),
+        };
+        match code {
+            0 => "no operation".to_string(),
+            1 => "add M to rA".to_string(),
+            2 => "subtract M from rA".to_string(),
+            3 => "multiply rA by M into rAX".to_string(),
+            4 => "divide rAX by M into rA with the remainder in rX".to_string(),
+            5 => [
+                "convert the characters of rAX into a number in rA",
+                "convert rA into characters in rAX",
+                "halt the machine",
+            ][field]
+                .to_string(),
+            6 => [
+                "shift rA left by M bytes",
+                "shift rA right by M bytes",
+                "shift rAX left by M bytes",
+                "shift rAX right by M bytes",
+                "shift rAX left circularly by M bytes",
+                "shift rAX right circularly by M bytes",
+            ][field]
+                .to_string(),
+            7 => "move F words from M to the location in rI1".to_string(),
+            8..=15 => format!("load M into {}", register(8)),
+            16..=23 => format!("load the negative of M into {}", register(16)),
+            24..=31 => format!("store {} into M", register(24)),
+            32 => "store rJ into M".to_string(),
+            33 => "store zero into M".to_string(),
+            34 => "jump to M if unit F is busy".to_string(),
+            35 => "control unit F".to_string(),
+            36 => "input from unit F into M".to_string(),
+            37 => "output from M to unit F".to_string(),
+            38 => "jump to M if unit F is ready".to_string(),
+            39 => [
+                "jump to M",
+                "jump to M without changing rJ",
+                "jump to M on overflow and clear it",
+                "jump to M if there is no overflow, otherwise clear it",
+                "jump to M if the comparison was less",
+                "jump to M if the comparison was equal",
+                "jump to M if the comparison was greater",
+                "jump to M if the comparison was greater or equal",
+                "jump to M if the comparison was unequal",
+                "jump to M if the comparison was less or equal",
+            ][field]
+                .to_string(),
+            40..=47 => format!(
+                "jump to M if {} is {}",
+                register(40),
+                [
+                    "negative",
+                    "zero",
+                    "positive",
+                    "nonnegative",
+                    "nonzero",
+                    "nonpositive"
+                ][field]
+            ),
+            48..=55 => [
+                format!("increase {} by M", register(48)),
+                format!("decrease {} by M", register(48)),
+                format!("enter M into {}", register(48)),
+                format!("enter the negative of M into {}", register(48)),
+            ][field]
+                .clone(),
+            _ => format!("compare {} with M", register(56)),
+        }
+    }
+
+    fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        OPERATIONS
+            .iter()
+            .map(|(operation, _, _, _)| *operation)
+            .find(|operation| operation.mnemonic() == mnemonic)
+    }
+
+    /// Whether F is a field specification of a word in memory
+    /// rather than a unit, a count or a part of the operation.
+    fn uses_field(self) -> bool {
+        matches!(self.code().0, 1..=4 | 8..=33 | 56..=63)
+    }
+
+    /// Decodes operation from its code and field, when the field selects the operation.
+    fn decode(code: Byte, field: Modification) -> Option<Self> {
+        let mut candidates = OPERATIONS.iter().filter(|(_, c, _, _)| *c == code.0);
+        let (first, _, _, _) = *candidates.next()?;
+        if candidates.next().is_none() {
+            return Some(first);
         }
+        OPERATIONS
+            .iter()
+            .find(|(operation, c, _, _)| *c == code.0 && operation.default_modification() == field)
+            .map(|(operation, _, _, _)| *operation)
     }
 }
+#[derive(Debug, PartialEq, Clone, Copy)]
 struct Instruction {
     operation: Operation,
     address: Address,
@@ -427,6 +997,51 @@ impl Instruction {
             modification,
         }
     }
+
+    fn field(&self) -> Modification {
+        self.modification
+            .unwrap_or_else(|| self.operation.default_modification())
+    }
+
+    fn unit(&self) -> u8 {
+        Byte::from(self.field()).0
+    }
+
+    fn time(&self) -> u64 {
+        match self.operation {
+            Operation::MOVE => self.operation.time() + 2 * Byte::from(self.field()).0 as u64,
+            operation => operation.time(),
+        }
+    }
+
+    /// Decodes instruction from a word of the form ±AA I F C.
+    fn decode(word: Word) -> Option<Self> {
+        let field = Modification::from(word.bytes[3]);
+        let operation = Operation::decode(word.bytes[4], field)?;
+        let index = IndexNumber::decode(word.bytes[2])?;
+        if operation.uses_field() && !field.is_valid() {
+            return None;
+        }
+        let address = Address {
+            sign: word.sign,
+            bytes: [word.bytes[0], word.bytes[1]],
+        };
+        Some(Instruction::new(operation, address, index, Some(field)))
+    }
+}
+impl From<Instruction> for Word {
+    fn from(instruction: Instruction) -> Self {
+        Word {
+            sign: instruction.address.sign,
+            bytes: [
+                instruction.address.bytes[0],
+                instruction.address.bytes[1],
+                Byte::new(instruction.index.map_or(0, |i| i as u8)),
+                instruction.field().into(),
+                instruction.operation.code(),
+            ],
+        }
+    }
 }
 
 impl Mix {
@@ -435,27 +1050,358 @@ impl Mix {
     }
 
     fn save_contents(&mut self, address: &Address, word: Word) {
-        self.memory[address.memory_index()] = word;
+        let location = address.memory_index();
+        let old = self.memory[location];
+        self.memory[location] = word;
+        self.watch_memory(location, old);
+    }
+
+    fn register(&self, register: Register) -> Word {
+        match register {
+            Register::A => self.a,
+            Register::X => self.x,
+            Register::I(IndexNumber::I1) => self.i1.into(),
+            Register::I(IndexNumber::I2) => self.i2.into(),
+            Register::I(IndexNumber::I3) => self.i3.into(),
+            Register::I(IndexNumber::I4) => self.i4.into(),
+            Register::I(IndexNumber::I5) => self.i5.into(),
+            Register::I(IndexNumber::I6) => self.i6.into(),
+            Register::J => self.j.into(),
+        }
+    }
+
+    fn set_register(&mut self, register: Register, word: Word) {
+        match register {
+            Register::A => self.a = word,
+            Register::X => self.x = word,
+            Register::I(IndexNumber::I1) => self.i1 = word.into(),
+            Register::I(IndexNumber::I2) => self.i2 = word.into(),
+            Register::I(IndexNumber::I3) => self.i3 = word.into(),
+            Register::I(IndexNumber::I4) => self.i4 = word.into(),
+            Register::I(IndexNumber::I5) => self.i5 = word.into(),
+            Register::I(IndexNumber::I6) => self.i6 = word.into(),
+            Register::J => self.j = word.into(),
+        }
+    }
+
+    fn is_watched(&self, location: usize) -> bool {
+        self.watchpoints.iter().any(|watchpoint| match watchpoint {
+            Watchpoint::Write(watched) | Watchpoint::Field(watched, _) => *watched == location,
+            Watchpoint::Register(_, _) => false,
+        })
+    }
+
+    /// Fires the watchpoints on the location, which was `old` before it was written.
+    fn watch_memory(&mut self, location: usize, old: Word) {
+        let new = self.memory[location];
+        for &watchpoint in &self.watchpoints {
+            match watchpoint {
+                Watchpoint::Write(watched) if watched == location => {
+                    self.changes.push(Change {
+                        watchpoint,
+                        old,
+                        new,
+                    });
+                }
+                Watchpoint::Field(watched, field)
+                    if watched == location && old.slice(field) != new.slice(field) =>
+                {
+                    self.changes.push(Change {
+                        watchpoint,
+                        old: old.slice(field),
+                        new: new.slice(field),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Fires the watchpoints on registers that reached their values, `watched` holds
+    /// the registers before the instruction.
+    fn watch_registers(&mut self, watched: Vec<(Watchpoint, Word)>) {
+        for (watchpoint, old) in watched {
+            if let Watchpoint::Register(register, value) = watchpoint {
+                let new = self.register(register);
+                if new.value() == value && old.value() != value {
+                    self.changes.push(Change {
+                        watchpoint,
+                        old,
+                        new,
+                    });
+                }
+            }
+        }
     }
 
     fn load(&self, instruction: Instruction) -> Word {
-        let operation = instruction.operation;
-        let field = instruction
-            .modification
-            .unwrap_or_else(|| operation.default_modification());
-        self.contents(&instruction.address).slice(field)
+        self.contents(&instruction.address)
+            .slice(instruction.field())
     }
 
     fn store(&mut self, word: Word, instruction: Instruction) {
         let cell = self.contents(&instruction.address);
-        let operation = instruction.operation;
-        let field = instruction
-            .modification
-            .unwrap_or_else(|| operation.default_modification());
-        self.save_contents(&instruction.address, cell.merge(word, field));
+        self.save_contents(&instruction.address, cell.merge(word, instruction.field()));
+    }
+
+    fn attach(&mut self, unit: u8, device: impl Device + 'static) {
+        self.attach_timed(unit, device, 0);
+    }
+
+    /// Attaches device that stays busy for `transfer_time` after every IN, OUT or IOC.
+    ///
+    /// Blocks are transferred as soon as the operation starts, only the busy interval is simulated.
+    fn attach_timed(&mut self, unit: u8, device: impl Device + 'static, transfer_time: u64) {
+        self.devices[unit as usize] = Some(Unit {
+            device: Box::new(device),
+            transfer_time,
+            busy_until: 0,
+        });
+    }
+
+    fn input(&mut self, instruction: Instruction) -> Result<(), Fault> {
+        let unit = instruction.unit();
+        let size = attached(&mut self.devices, unit)?.device.block_size();
+        let block = block(&instruction.address, size)?;
+        let watched: Vec<(usize, Word)> = block
+            .clone()
+            .filter(|&location| self.is_watched(location))
+            .map(|location| (location, self.memory[location]))
+            .collect();
+        let attached = attached(&mut self.devices, unit)?;
+        self.clock = attached.start(self.clock);
+        attached.device.position(self.x);
+        let result = attached
+            .device
+            .input(&mut self.memory[block])
+            .map_err(|e| Fault::Device(unit, e));
+        for (location, old) in watched {
+            self.watch_memory(location, old);
+        }
+        result
+    }
+
+    fn output(&mut self, instruction: Instruction) -> Result<(), Fault> {
+        let unit = instruction.unit();
+        let attached = attached(&mut self.devices, unit)?;
+        let block = block(&instruction.address, attached.device.block_size())?;
+        self.clock = attached.start(self.clock);
+        attached.device.position(self.x);
+        attached
+            .device
+            .output(&self.memory[block])
+            .map_err(|e| Fault::Device(unit, e))
+    }
+
+    fn control(&mut self, instruction: Instruction) -> Result<(), Fault> {
+        let unit = instruction.unit();
+        let attached = attached(&mut self.devices, unit)?;
+        self.clock = attached.start(self.clock);
+        attached.device.position(self.x);
+        attached
+            .device
+            .control(instruction.address.value())
+            .map_err(|e| Fault::Device(unit, e))
+    }
+
+    /// Replaces address of the instruction with the effective address M.
+    fn indexed(&self, instruction: Instruction) -> Result<Instruction, Fault> {
+        let index = match instruction.index {
+            None => return Ok(instruction),
+            Some(IndexNumber::I1) => self.i1,
+            Some(IndexNumber::I2) => self.i2,
+            Some(IndexNumber::I3) => self.i3,
+            Some(IndexNumber::I4) => self.i4,
+            Some(IndexNumber::I5) => self.i5,
+            Some(IndexNumber::I6) => self.i6,
+        };
+        let m = instruction.address.value() + Word::from(index).value() as i16;
+        if m.abs() >= BYTE as i16 * BYTE as i16 {
+            return Err(Fault::InvalidAddress(m));
+        }
+        let address = if m == 0 {
+            Address {
+                sign: instruction.address.sign,
+                ..Address::new(0)
+            }
+        } else {
+            Address::new(m)
+        };
+        Ok(Instruction {
+            address,
+            index: None,
+            ..instruction
+        })
+    }
+
+    fn add_overflowing(&mut self, a: Word, b: Word) -> Word {
+        let (sum, overflows) = a.overflowing_add(b);
+        if overflows {
+            self.overflow = Toggle::On;
+        }
+        sum
+    }
+
+    fn divide(&mut self, instruction: Instruction) {
+        let divisor = self.load(instruction);
+        if divisor.magnitude() == 0 || self.a.magnitude() >= divisor.magnitude() {
+            self.overflow = Toggle::On;
+            return;
+        }
+        let modulus = (BYTE as u64).pow(WORD_BYTES as u32);
+        let dividend = self.a.magnitude() * modulus + self.x.magnitude();
+        let sign = if self.a.sign == divisor.sign {
+            Sign::Plus
+        } else {
+            Sign::Minus
+        };
+        self.x = Word::from_magnitude(self.a.sign, dividend % divisor.magnitude());
+        self.a = Word::from_magnitude(sign, dividend / divisor.magnitude());
+    }
+
+    fn num(&mut self) {
+        let modulus = (BYTE as u64).pow(WORD_BYTES as u32);
+        let value = self
+            .a
+            .bytes
+            .iter()
+            .chain(self.x.bytes.iter())
+            .fold(0, |acc, b| acc * 10 + b.0 as u64 % 10);
+        self.a = Word::from_magnitude(self.a.sign, value % modulus);
+    }
+
+    fn char(&mut self) {
+        let mut value = self.a.magnitude();
+        let digits = self.a.bytes.iter_mut().chain(self.x.bytes.iter_mut());
+        for byte in digits.rev() {
+            *byte = Byte::new(30 + (value % 10) as u8);
+            value /= 10;
+        }
+    }
+
+    fn shift(&mut self, instruction: Instruction) -> Result<(), Fault> {
+        let m = instruction.address.value();
+        if m < 0 {
+            return Err(Fault::InvalidAddress(m));
+        }
+        let count = m as usize;
+        let mut ax = [Byte::default(); 2 * WORD_BYTES as usize];
+        let len = ax.len();
+        ax[..5].copy_from_slice(&self.a.bytes);
+        ax[5..].copy_from_slice(&self.x.bytes);
+        match instruction.operation {
+            Operation::SLA => shift_left(&mut ax[..5], count),
+            Operation::SRA => shift_right(&mut ax[..5], count),
+            Operation::SLAX => shift_left(&mut ax, count),
+            Operation::SRAX => shift_right(&mut ax, count),
+            Operation::SLC => ax.rotate_left(count % len),
+            Operation::SRC => ax.rotate_right(count % len),
+            _ => {}
+        }
+        self.a.bytes.copy_from_slice(&ax[..5]);
+        self.x.bytes.copy_from_slice(&ax[5..]);
+        Ok(())
+    }
+
+    fn move_words(&mut self, instruction: Instruction) -> Result<(), Fault> {
+        let count = Byte::from(instruction.field()).0 as i16;
+        for k in 0..count {
+            let from = memory_index(instruction.address.value() + k)?;
+            let to = memory_index(Word::from(self.i1).value() as i16)?;
+            self.save_contents(&Address::new(to as i16), self.memory[from]);
+            self.i1 = Index::from(Word::from(Address::new(to as i16 + 1)));
+        }
+        Ok(())
+    }
+
+    fn compare(&mut self, register: Word, instruction: Instruction) {
+        let left = register.slice(instruction.field()).value();
+        let right = self.load(instruction).value();
+        self.comparison_indicator = match left.cmp(&right) {
+            std::cmp::Ordering::Less => Comparison::Less,
+            std::cmp::Ordering::Equal => Comparison::Equal,
+            std::cmp::Ordering::Greater => Comparison::Greater,
+        };
+    }
+
+    fn busy(&mut self, unit: u8) -> Result<bool, Fault> {
+        let clock = self.clock;
+        attached(&mut self.devices, unit).map(|attached| clock < attached.busy_until)
+    }
+
+    fn go_to(&mut self, address: &Address) {
+        match memory_index(address.value()) {
+            Ok(location) => self.program_counter = location,
+            Err(fault) => self.fault = Some(fault),
+        }
+    }
+
+    fn jump_if(&mut self, condition: bool, address: &Address) {
+        if condition {
+            self.j = Jump::from(Word::from(Address::new(self.program_counter as i16)));
+            self.go_to(address);
+        }
+    }
+
+    /// Jumps when the register is negative, zero, positive, nonnegative, nonzero
+    /// or nonpositive, as the variant of the operation selects.
+    fn jump_on(&mut self, register: Register, variant: u8, address: &Address) {
+        let value = self.register(register).value();
+        let condition = match variant {
+            0 => value < 0,
+            1 => value == 0,
+            2 => value > 0,
+            3 => value >= 0,
+            4 => value != 0,
+            _ => value <= 0,
+        };
+        self.jump_if(condition, address);
+    }
+
+    /// Increases, decreases, enters or enters the negative of the address into the register,
+    /// as the variant of the operation selects.
+    fn transfer(&mut self, register: Register, variant: u8, address: Address) {
+        let m = Word::from(address);
+        match variant {
+            0 => self.increment(register, m),
+            1 => self.increment(register, -m),
+            2 => self.set_register(register, m),
+            _ => self.set_register(register, -m),
+        }
+    }
+
+    /// Adds to the register, only A and X turn on the overflow toggle.
+    fn increment(&mut self, register: Register, m: Word) {
+        let old = self.register(register);
+        let sum = match register {
+            Register::A | Register::X => self.add_overflowing(old, m),
+            Register::I(_) | Register::J => old.overflowing_add(m).0,
+        };
+        self.set_register(register, sum);
     }
 
     fn exec(mut self, instruction: Instruction) -> Self {
+        let instruction = match self.indexed(instruction) {
+            Ok(instruction) => instruction,
+            Err(fault) => {
+                self.fault = Some(fault);
+                return self;
+            }
+        };
+        if instruction.operation.uses_field() {
+            if let Err(fault) = memory_index(instruction.address.value()) {
+                self.fault = Some(fault);
+                return self;
+            }
+        }
+        let watched: Vec<(Watchpoint, Word)> = self
+            .watchpoints
+            .iter()
+            .filter_map(|&watchpoint| match watchpoint {
+                Watchpoint::Register(register, _) => Some((watchpoint, self.register(register))),
+                _ => None,
+            })
+            .collect();
         match instruction.operation {
             Operation::LDA => {
                 self.a = self.load(instruction);
@@ -536,31 +1482,236 @@ impl Mix {
                 self.store(Word::default(), instruction);
             }
             Operation::ADD => {
-                let (sum, overflows) = self.a.overflowing_add(self.load(instruction));
-                self.a = sum;
-                self.overflow = Toggle::from(overflows);
+                self.a = self.add_overflowing(self.a, self.load(instruction));
             }
             Operation::SUB => {
-                let (sum, overflows) = self.a.overflowing_add(-self.load(instruction));
-                self.a = sum;
-                self.overflow = Toggle::from(overflows);
+                self.a = self.add_overflowing(self.a, -self.load(instruction));
             }
             Operation::MUL => {
                 let (a, x, overflows) = self.a.overflowing_mul(self.load(instruction));
                 self.a = a;
                 self.x = x;
-                self.overflow = Toggle::from(overflows);
+                if overflows {
+                    self.overflow = Toggle::On;
+                }
+            }
+            Operation::IN => {
+                if let Err(fault) = self.input(instruction) {
+                    self.fault = Some(fault);
+                }
+            }
+            Operation::IOC => {
+                if let Err(fault) = self.control(instruction) {
+                    self.fault = Some(fault);
+                }
+            }
+            Operation::OUT => {
+                if let Err(fault) = self.output(instruction) {
+                    self.fault = Some(fault);
+                }
+            }
+            Operation::NOP => {}
+            Operation::DIV => {
+                self.divide(instruction);
+            }
+            Operation::NUM => {
+                self.num();
+            }
+            Operation::CHAR => {
+                self.char();
+            }
+            Operation::HLT => {
+                self.halted = true;
+            }
+            Operation::SLA
+            | Operation::SRA
+            | Operation::SLAX
+            | Operation::SRAX
+            | Operation::SLC
+            | Operation::SRC => {
+                if let Err(fault) = self.shift(instruction) {
+                    self.fault = Some(fault);
+                }
+            }
+            Operation::MOVE => {
+                if let Err(fault) = self.move_words(instruction) {
+                    self.fault = Some(fault);
+                }
+            }
+            Operation::JBUS => match self.busy(instruction.unit()) {
+                Ok(busy) => self.jump_if(busy, &instruction.address),
+                Err(fault) => self.fault = Some(fault),
+            },
+            Operation::JRED => match self.busy(instruction.unit()) {
+                Ok(busy) => self.jump_if(!busy, &instruction.address),
+                Err(fault) => self.fault = Some(fault),
+            },
+            Operation::JMP => {
+                self.jump_if(true, &instruction.address);
+            }
+            Operation::JSJ => {
+                self.go_to(&instruction.address);
+            }
+            Operation::JOV => {
+                let on = self.overflow == Toggle::On;
+                self.overflow = Toggle::Off;
+                self.jump_if(on, &instruction.address);
+            }
+            Operation::JNOV => {
+                let off = self.overflow == Toggle::Off;
+                self.overflow = Toggle::Off;
+                self.jump_if(off, &instruction.address);
+            }
+            Operation::JL => {
+                let condition = self.comparison_indicator == Comparison::Less;
+                self.jump_if(condition, &instruction.address);
+            }
+            Operation::JE => {
+                let condition = self.comparison_indicator == Comparison::Equal;
+                self.jump_if(condition, &instruction.address);
+            }
+            Operation::JG => {
+                let condition = self.comparison_indicator == Comparison::Greater;
+                self.jump_if(condition, &instruction.address);
+            }
+            Operation::JGE => {
+                let condition = self.comparison_indicator != Comparison::Less;
+                self.jump_if(condition, &instruction.address);
+            }
+            Operation::JNE => {
+                let condition = self.comparison_indicator != Comparison::Equal;
+                self.jump_if(condition, &instruction.address);
+            }
+            Operation::JLE => {
+                let condition = self.comparison_indicator != Comparison::Greater;
+                self.jump_if(condition, &instruction.address);
+            }
+            // Codes 40–63 jump on, transfer addresses to and compare a register.
+            operation => {
+                let register = Register::of_code(operation.code());
+                let variant = Byte::from(operation.default_modification()).0;
+                match operation.code().0 {
+                    40..=47 => self.jump_on(register, variant, &instruction.address),
+                    48..=55 => self.transfer(register, variant, instruction.address),
+                    _ => self.compare(self.register(register), instruction),
+                }
+            }
+        };
+        self.watch_registers(watched);
+        self.clock += instruction.time();
+        self
+    }
+
+    /// Copies the image into memory and continues from its start location.
+    fn load_image(&mut self, image: &Image) {
+        for (&location, &word) in &image.words {
+            self.memory[location] = word;
+        }
+        self.program_counter = image.start;
+        self.halted = false;
+    }
+
+    /// Presses the GO button: reads a card into locations 0–15,
+    /// waits for the read to finish and continues from location 0.
+    fn go(mut self) -> Self {
+        let read = Instruction::new(
+            Operation::IN,
+            Address::new(0),
+            None,
+            Some(Modification::from(Byte::new(device::CARD_READER))),
+        );
+        if let Err(fault) = self.input(read) {
+            self.fault = Some(fault);
+            return self;
+        }
+        if let Some(reader) = &self.devices[device::CARD_READER as usize] {
+            self.clock = reader.busy_until;
+        }
+        self.program_counter = 0;
+        self.halted = false;
+        self
+    }
+
+    /// Executes the instruction at the program counter.
+    fn step(mut self) -> Self {
+        if self.halted || self.fault.is_some() {
+            return self;
+        }
+        self.changes.clear();
+        let location = self.program_counter;
+        let instruction = match self.memory.get(location).map(|w| Instruction::decode(*w)) {
+            Some(Some(instruction)) => instruction,
+            Some(None) => {
+                self.fault = Some(Fault::InvalidInstruction(location));
+                return self;
+            }
+            None => {
+                self.fault = Some(Fault::InvalidAddress(location as i16));
+                return self;
             }
         };
+        self.program_counter += 1;
+        let mut mix = self.exec(instruction);
+        if mix.fault.is_some() {
+            mix.program_counter = location;
+        }
+        mix
+    }
+
+    /// Executes instructions until HLT or a fault.
+    #[cfg(test)]
+    fn run(mut self) -> Self {
+        while !self.halted && self.fault.is_none() {
+            self = self.step();
+        }
         self
     }
 }
 
-fn main() {}
+fn attached(devices: &mut [Option<Unit>], unit: u8) -> Result<&mut Unit, Fault> {
+    match devices.get_mut(unit as usize) {
+        Some(Some(attached)) => Ok(attached),
+        _ => Err(Fault::NoDevice(unit)),
+    }
+}
+
+fn block(address: &Address, size: usize) -> Result<std::ops::Range<usize>, Fault> {
+    let start = memory_index(address.value())?;
+    if start + size > MEMORY_SIZE {
+        return Err(Fault::InvalidAddress(address.value()));
+    }
+    Ok(start..start + size)
+}
+
+fn memory_index(m: i16) -> Result<usize, Fault> {
+    if m < 0 || m as usize >= MEMORY_SIZE {
+        return Err(Fault::InvalidAddress(m));
+    }
+    Ok(m as usize)
+}
+
+fn shift_left(bytes: &mut [Byte], count: usize) {
+    let count = count.min(bytes.len());
+    bytes.rotate_left(count);
+    let len = bytes.len();
+    bytes[len - count..].fill(Byte::default());
+}
+
+fn shift_right(bytes: &mut [Byte], count: usize) {
+    let count = count.min(bytes.len());
+    bytes.rotate_right(count);
+    bytes[..count].fill(Byte::default());
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(cli::main(&args));
+}
 
 #[cfg(test)]
 mod spec {
     use super::*;
+    use crate::dsl::mix;
     use Operation::*;
     use Sign::*;
     use Toggle::*;
@@ -1107,6 +2258,7 @@ mod spec {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn add_overflow() {
         assert(w(5, 1, 0, 2, 1), w(5, 1, 0, 3, 2), w(10, 2, 0, 5, 3), Off);
         assert(
@@ -1147,6 +2299,7 @@ mod spec {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn add_sign() {
         assert(w(0, 0, 0, 1, 0), -w(0, 0, 0, 0, 1), w(0, 0, 0, 0, BYTE - 1));
         assert(-w(0, 0, 0, 0, 1), -w(0, 0, 0, 0, 1), -w(0, 0, 0, 0, 2));
@@ -1168,6 +2321,7 @@ mod spec {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn add_field() {
         assert(w(14, 13, 12, 11, 10), fields(1, 1), w(5, 4, 3, 2, 15));
         assert(w(14, 13, 12, 11, 10), fields(3, 3), w(5, 4, 3, 2, 13));
@@ -1188,6 +2342,7 @@ mod spec {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn add_example() {
         let mut mix = Mix::default();
         mix.a = w(1234, 1, 150);
@@ -1211,6 +2366,7 @@ mod spec {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn sub_overflow() {
         assert(w(5, 1, 0, 2, 1), -w(5, 1, 0, 3, 2), w(10, 2, 0, 5, 3), Off);
         assert(
@@ -1251,6 +2407,7 @@ mod spec {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn sub_sign() {
         assert(w(0, 0, 0, 1, 0), w(0, 0, 0, 0, 1), w(0, 0, 0, 0, BYTE - 1));
         assert(-w(0, 0, 0, 0, 1), w(0, 0, 0, 0, 1), -w(0, 0, 0, 0, 2));
@@ -1272,6 +2429,7 @@ mod spec {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn sub_field() {
         assert(-w(14, 13, 12, 11, 10), fields(1, 1), w(50, 50, 50, 50, 36));
         assert(-w(14, 13, 12, 11, 10), fields(3, 3), w(50, 50, 50, 50, 38));
@@ -1292,6 +2450,7 @@ mod spec {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn sub_example() {
         let mut mix = Mix::default();
         mix.a = -w(1234, 0, 9);
@@ -1315,6 +2474,7 @@ mod spec {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn mul_examples() {
         let mut mix = Mix::default();
         mix.a = w(1, 1, 1, 1, 1);
@@ -1356,4 +2516,819 @@ mod spec {
         assert_eq!(mix.a, -w(0, 0, 0, 0, 0));
         assert_eq!(mix.x, -w(0, 0, 0, 0, 1));
     }
+
+    fn unit(u: u8) -> Option<Modification> {
+        Some(Modification::from(Byte::new(u)))
+    }
+
+    fn text(s: &str) -> Vec<Word> {
+        let mut bytes: Vec<u8> = s.chars().map(|c| Byte::from_char(c).unwrap().0).collect();
+        bytes.resize(120, 0);
+        bytes
+            .chunks(5)
+            .map(|b| w(b[0], b[1], b[2], b[3], b[4]))
+            .collect()
+    }
+
+    #[test]
+    fn character_codes() {
+        assert_eq!(Byte::from_char(' '), Some(Byte::new(0)));
+        assert_eq!(Byte::from_char('A'), Some(Byte::new(1)));
+        assert_eq!(Byte::from_char('Δ'), Some(Byte::new(10)));
+        assert_eq!(Byte::from_char('0'), Some(Byte::new(30)));
+        assert_eq!(Byte::from_char('\''), Some(Byte::new(55)));
+        assert_eq!(Byte::from_char('a'), None);
+        for code in 0..56 {
+            let byte = Byte::new(code);
+            assert_eq!(Byte::from_char(byte.char()), Some(byte));
+        }
+    }
+
+    #[test]
+    fn out_prints_line() {
+        let printed = device::Lines::default();
+        let mut mix = Mix::default();
+        mix.attach(
+            18,
+            device::LinePrinter::new(printed.clone(), 60, device::Paging::Separator),
+        );
+        mix.memory[2000..2024].copy_from_slice(&text("PRIMES"));
+        mix.memory[3000..3024].copy_from_slice(&text("0002 0003"));
+
+        let mix = mix.exec(instruction(IOC, 0, None, unit(18)));
+        let mix = mix.exec(instruction(OUT, 2000, None, unit(18)));
+        let mix = mix.exec(instruction(IOC, 0, None, unit(18)));
+        let mix = mix.exec(instruction(OUT, 3000, None, unit(18)));
+
+        assert!(mix.fault.is_none());
+        assert_eq!(
+            printed.lines(),
+            vec!["PRIMES", &"-".repeat(120), "0002 0003"]
+        );
+    }
+
+    #[test]
+    fn out_faults() {
+        let mix = Mix::default().exec(instruction(OUT, 2000, None, unit(18)));
+        assert!(matches!(mix.fault, Some(Fault::NoDevice(18))));
+
+        let mut mix = Mix::default();
+        mix.attach(
+            18,
+            device::LinePrinter::new(vec![], 60, device::Paging::FormFeed),
+        );
+        let mix = mix.exec(instruction(OUT, 3990, None, unit(18)));
+        assert!(matches!(mix.fault, Some(Fault::InvalidAddress(3990))));
+
+        let mut mix = Mix::default();
+        mix.attach(
+            18,
+            device::LinePrinter::new(vec![], 60, device::Paging::FormFeed),
+        );
+        let mix = mix.exec(instruction(IOC, 5, None, unit(18)));
+        assert!(matches!(mix.fault, Some(Fault::Device(18, _))));
+    }
+
+    #[test]
+    fn in_reads_block() {
+        let mut mix = Mix::default();
+        let tape = std::io::Cursor::new("RIGHT\n".to_string());
+        mix.attach(20, device::PaperTape::new(tape));
+        mix.memory[1000..1015].copy_from_slice(&[w(1, 2, 3, 4, 5); 15]);
+
+        let mix = mix.exec(instruction(IN, 1000, None, unit(20)));
+
+        assert!(mix.fault.is_none());
+        assert_eq!(mix.memory[1000..1014], text("RIGHT")[..14]);
+        assert_eq!(mix.memory[1014], w(1, 2, 3, 4, 5), "outside of block");
+    }
+
+    #[test]
+    fn in_faults_at_end_of_input() {
+        let mut mix = Mix::default();
+        let typewriter = device::Typewriter::new(std::io::Cursor::new(String::new()), vec![]);
+        mix.attach(19, typewriter);
+
+        let mix = mix.exec(instruction(IN, 1000, None, unit(19)));
+
+        assert!(matches!(mix.fault, Some(Fault::Device(19, _))));
+    }
+
+    fn index(i: u8) -> Option<IndexNumber> {
+        IndexNumber::decode(Byte::new(i)).unwrap()
+    }
+
+    fn load_program(mix: &mut Mix, program: &[Instruction]) {
+        for (location, instruction) in program.iter().enumerate() {
+            mix.memory[location] = Word::from(*instruction);
+        }
+    }
+
+    #[test]
+    fn instruction_encoding() {
+        for &(operation, code, f, _) in OPERATIONS.iter() {
+            let instruction = instruction(operation, -1234, index(3), None);
+            let word = Word::from(instruction);
+
+            assert_eq!(word, -w(19, 18, 3, f, code));
+            assert_eq!(
+                Instruction::decode(word),
+                Some(Instruction {
+                    modification: Some(operation.default_modification()),
+                    ..instruction
+                }),
+                "{:?} should decode back",
+                operation
+            );
+        }
+    }
+
+    #[test]
+    fn operations_table_follows_variants() {
+        for (i, &(operation, _, _, _)) in OPERATIONS.iter().enumerate() {
+            assert_eq!(operation as usize, i, "{:?}", operation);
+        }
+    }
+
+    #[test]
+    fn operations_describe_themselves() {
+        assert_eq!(LDX.description(), "load M into rX");
+        assert_eq!(ST3.description(), "store rI3 into M");
+        assert_eq!(SRAX.description(), "shift rAX right by M bytes");
+        assert_eq!(
+            JGE.description(),
+            "jump to M if the comparison was greater or equal"
+        );
+        assert_eq!(JXNP.description(), "jump to M if rX is nonpositive");
+        assert_eq!(ENN5.description(), "enter the negative of M into rI5");
+        assert_eq!(CMP1.description(), "compare rI1 with M");
+        assert_eq!(HLT.description(), "halt the machine");
+    }
+
+    #[test]
+    fn invalid_instructions() {
+        assert_eq!(
+            Instruction::decode(w(0, 0, 0, 3, 5)),
+            None,
+            "no such F for C=5"
+        );
+        assert_eq!(
+            Instruction::decode(w(0, 0, 0, 10, 39)),
+            None,
+            "no such jump"
+        );
+        assert_eq!(Instruction::decode(w(0, 0, 7, 5, 8)), None, "no such index");
+        assert_eq!(Instruction::decode(w(0, 0, 0, 26, 8)), None, "field (3:2)");
+        assert_eq!(Instruction::decode(w(0, 0, 0, 6, 8)), None, "field (0:6)");
+        assert_eq!(
+            Instruction::decode(w(0, 0, 0, 7, 37)),
+            Some(instruction(OUT, 0, None, unit(7))),
+            "F is a unit for OUT"
+        );
+    }
+
+    #[test]
+    fn indexing() {
+        let mut mix = Mix::default();
+        mix.memory[1005] = w(0, 0, 0, 0, 5);
+        mix.memory[995] = w(0, 0, 0, 0, 6);
+        mix.i1 = Index::new(Plus, 0, 5);
+        mix.i2 = Index::new(Minus, 0, 5);
+
+        let mix = mix.exec(instruction(LDA, 1000, index(1), None));
+        assert_eq!(mix.a, w(0, 0, 0, 0, 5));
+        let mix = mix.exec(instruction(LDX, 1000, index(2), None));
+        assert_eq!(mix.x, w(0, 0, 0, 0, 6));
+        let mix = mix.exec(instruction(LDA, 3, index(2), None));
+        assert!(matches!(mix.fault, Some(Fault::InvalidAddress(-2))));
+    }
+
+    #[test]
+    fn enter() {
+        let mix = Mix {
+            i3: Index::new(Minus, 0, 10),
+            ..Mix::default()
+        };
+
+        let mix = mix.exec(instruction(ENTA, 2000, None, None));
+        assert_eq!(mix.a, w(0, 0, 0, 31, 16));
+        let mix = mix.exec(instruction(ENNX, 3, None, None));
+        assert_eq!(mix.x, -w(0, 0, 0, 0, 3));
+        let mix = mix.exec(instruction(ENT1, 20, index(3), None));
+        assert_eq!(mix.i1, Index::new(Plus, 0, 10));
+        let mix = mix.exec(instruction(ENTA, 10, index(3), None));
+        assert_eq!(mix.a, w(0, 0, 0, 0, 0));
+        let mix = mix.exec(instruction(ENT2, -10, index(3), None));
+        assert_eq!(mix.i2, Index::new(Minus, 0, 20));
+
+        let mix = Mix {
+            a: w(1, 2, 3, 4, 5),
+            ..Mix::default()
+        };
+        let mix = mix.exec(Instruction::new(
+            ENTA,
+            Address {
+                sign: Minus,
+                ..Address::new(0)
+            },
+            None,
+            None,
+        ));
+        assert_eq!(mix.a, -w(0, 0, 0, 0, 0), "ENTA -0 keeps the sign");
+    }
+
+    #[test]
+    fn increase_and_decrease() {
+        let mix = Mix {
+            a: w(BYTE - 1, BYTE - 1, BYTE - 1, BYTE - 1, BYTE - 1),
+            x: w(0, 0, 0, 0, 1),
+            i4: Index::new(Plus, 1, 0),
+            ..Mix::default()
+        };
+
+        let mix = mix.exec(instruction(INCA, 1, None, None));
+        assert_eq!(mix.a, w(0, 0, 0, 0, 0));
+        assert_eq!(mix.overflow, On);
+        let mix = mix.exec(instruction(DECX, 2, None, None));
+        assert_eq!(mix.x, -w(0, 0, 0, 0, 1));
+        let mix = mix.exec(instruction(INC4, 1, None, None));
+        assert_eq!(mix.i4, Index::new(Plus, 1, 1));
+        let mix = mix.exec(instruction(DEC4, 66, None, None));
+        assert_eq!(mix.i4, Index::new(Minus, 0, 1));
+        assert_eq!(mix.overflow, On, "stays on");
+    }
+
+    #[test]
+    fn compare() {
+        assert(
+            CMPA,
+            w(0, 0, 0, 0, 5),
+            w(0, 0, 0, 0, 6),
+            None,
+            Comparison::Less,
+        );
+        assert(
+            CMPA,
+            w(0, 0, 0, 0, 7),
+            w(0, 0, 0, 0, 6),
+            None,
+            Comparison::Greater,
+        );
+        assert(
+            CMPA,
+            -w(0, 0, 0, 0, 0),
+            w(0, 0, 0, 0, 0),
+            None,
+            Comparison::Equal,
+        );
+        assert(
+            CMPX,
+            -w(0, 0, 0, 0, 7),
+            w(0, 0, 0, 0, 6),
+            None,
+            Comparison::Less,
+        );
+        assert(
+            CMPX,
+            -w(9, 0, 0, 0, 7),
+            w(1, 0, 0, 0, 6),
+            fields(5, 5),
+            Comparison::Greater,
+        );
+        assert(
+            CMPX,
+            -w(9, 0, 0, 0, 7),
+            w(1, 0, 0, 0, 6),
+            fields(0, 0),
+            Comparison::Equal,
+        );
+        assert(
+            CMP2,
+            w(0, 0, 0, 1, 7),
+            w(0, 0, 0, 1, 7),
+            None,
+            Comparison::Equal,
+        );
+        fn assert(
+            operation: Operation,
+            register: Word,
+            memory: Word,
+            f: Option<Modification>,
+            expected: Comparison,
+        ) {
+            let mut mix = Mix {
+                a: register,
+                x: register,
+                i2: Index::from(register),
+                ..Mix::default()
+            };
+            mix.memory[1000] = memory;
+
+            let mix = mix.exec(instruction(operation, 1000, None, f));
+
+            assert_eq!(mix.comparison_indicator, expected, "{:?}", operation);
+        }
+    }
+
+    #[test]
+    fn jumps() {
+        let mix = Mix {
+            program_counter: 101,
+            ..Mix::default()
+        };
+
+        let mix = mix.exec(instruction(JMP, 2000, None, None));
+        assert_eq!(mix.program_counter, 2000);
+        assert_eq!(mix.j, Jump::new(1, 37));
+        let mix = mix.exec(instruction(JSJ, 3000, None, None));
+        assert_eq!(mix.program_counter, 3000);
+        assert_eq!(mix.j, Jump::new(1, 37), "JSJ keeps J");
+
+        let mix = Mix {
+            overflow: On,
+            ..Mix::default()
+        };
+        let mix = mix.exec(instruction(JNOV, 2000, None, None));
+        assert_eq!((mix.program_counter, mix.overflow), (0, Off));
+        let mix = mix.exec(instruction(JNOV, 2000, None, None));
+        assert_eq!(mix.program_counter, 2000);
+        let mut mix = mix;
+        mix.overflow = On;
+        let mix = mix.exec(instruction(JOV, 3000, None, None));
+        assert_eq!((mix.program_counter, mix.overflow), (3000, Off));
+    }
+
+    #[test]
+    fn comparison_jumps() {
+        assert(JL, &[Comparison::Less]);
+        assert(JE, &[Comparison::Equal]);
+        assert(JG, &[Comparison::Greater]);
+        assert(JGE, &[Comparison::Equal, Comparison::Greater]);
+        assert(JNE, &[Comparison::Less, Comparison::Greater]);
+        assert(JLE, &[Comparison::Less, Comparison::Equal]);
+        fn assert(operation: Operation, jumps: &[Comparison]) {
+            for &comparison in &[Comparison::Less, Comparison::Equal, Comparison::Greater] {
+                let mix = Mix {
+                    comparison_indicator: comparison,
+                    ..Mix::default()
+                };
+
+                let mix = mix.exec(instruction(operation, 2000, None, None));
+
+                let jumped = mix.program_counter == 2000;
+                assert_eq!(jumped, jumps.contains(&comparison), "{:?}", operation);
+            }
+        }
+    }
+
+    #[test]
+    fn register_jumps() {
+        assert(JAN, JAZ, JAP, JANN, JANZ, JANP, |mix, w| mix.a = w);
+        assert(JXN, JXZ, JXP, JXNN, JXNZ, JXNP, |mix, w| mix.x = w);
+        assert(J1N, J1Z, J1P, J1NN, J1NZ, J1NP, |mix, w| mix.i1 = w.into());
+        assert(J6N, J6Z, J6P, J6NN, J6NZ, J6NP, |mix, w| mix.i6 = w.into());
+        fn assert(
+            n: Operation,
+            z: Operation,
+            p: Operation,
+            nn: Operation,
+            nz: Operation,
+            np: Operation,
+            set: fn(&mut Mix, Word),
+        ) {
+            let negative = -w(0, 0, 0, 0, 1);
+            let zero = -w(0, 0, 0, 0, 0);
+            let positive = w(0, 0, 0, 0, 1);
+            for &(operation, jumps) in &[
+                (n, [true, false, false]),
+                (z, [false, true, false]),
+                (p, [false, false, true]),
+                (nn, [false, true, true]),
+                (nz, [true, false, true]),
+                (np, [true, true, false]),
+            ] {
+                for (&value, &jump) in [negative, zero, positive].iter().zip(jumps.iter()) {
+                    let mut mix = Mix::default();
+                    set(&mut mix, value);
+
+                    let mix = mix.exec(instruction(operation, 2000, None, None));
+
+                    let jumped = mix.program_counter == 2000;
+                    assert_eq!(jumped, jump, "{:?} for {:?}", operation, value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn div_examples() {
+        let mut mix = Mix {
+            a: w(0, 0, 0, 0, 0),
+            x: w(0, 0, 0, 0, 17),
+            ..Mix::default()
+        };
+        mix.memory[1000] = w(0, 0, 0, 0, 3);
+
+        let mix = mix.exec(instruction(DIV, 1000, None, None));
+
+        assert_eq!(mix.a, w(0, 0, 0, 0, 5));
+        assert_eq!(mix.x, w(0, 0, 0, 0, 2));
+        assert_eq!(mix.overflow, Off);
+
+        let mut mix = Mix {
+            a: -w(0, 0, 0, 0, 0),
+            x: w(0, 0, 0, 0, 17),
+            ..Mix::default()
+        };
+        mix.memory[1000] = w(0, 0, 0, 0, 3);
+
+        let mix = mix.exec(instruction(DIV, 1000, None, None));
+
+        assert_eq!(mix.a, -w(0, 0, 0, 0, 5));
+        assert_eq!(mix.x, -w(0, 0, 0, 0, 2));
+
+        let mut mix = Mix {
+            a: w(0, 0, 0, 0, 3),
+            ..Mix::default()
+        };
+        mix.memory[1000] = w(0, 0, 0, 0, 3);
+
+        let mix = mix.exec(instruction(DIV, 1000, None, None));
+
+        assert_eq!(mix.overflow, On);
+    }
+
+    #[test]
+    fn shifts() {
+        let mix = Mix {
+            a: w(1, 2, 3, 4, 5),
+            x: -w(6, 7, 8, 9, 10),
+            ..Mix::default()
+        };
+
+        let mix = mix.exec(instruction(SRAX, 1, None, None));
+        assert_eq!((mix.a, mix.x), (w(0, 1, 2, 3, 4), -w(5, 6, 7, 8, 9)));
+        let mix = mix.exec(instruction(SLA, 2, None, None));
+        assert_eq!((mix.a, mix.x), (w(2, 3, 4, 0, 0), -w(5, 6, 7, 8, 9)));
+        let mix = mix.exec(instruction(SRC, 4, None, None));
+        assert_eq!((mix.a, mix.x), (w(6, 7, 8, 9, 2), -w(3, 4, 0, 0, 5)));
+        let mix = mix.exec(instruction(SRA, 2, None, None));
+        assert_eq!((mix.a, mix.x), (w(0, 0, 6, 7, 8), -w(3, 4, 0, 0, 5)));
+        let mix = mix.exec(instruction(SLC, 501, None, None));
+        assert_eq!((mix.a, mix.x), (w(0, 6, 7, 8, 3), -w(4, 0, 0, 5, 0)));
+        let mix = mix.exec(instruction(SLAX, 12, None, None));
+        assert_eq!((mix.a, mix.x), (w(0, 0, 0, 0, 0), -w(0, 0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn watchpoints_record_changes() {
+        let mut mix = Mix::default();
+        mix.load_image(&mix! {
+                   ORIG 100;
+            START: ENTA 5;
+                   STA  2000;
+                   STA  2001(4:5);
+                   STA  2001(1:2);
+                   ENTA 5;
+                   ENT1 2000;
+                   MOVE 2000(1);
+                   HLT;
+                   END  START;
+        });
+        mix.watchpoints = vec![
+            Watchpoint::Write(2000),
+            Watchpoint::Field(2001, Modification::field(1, 2)),
+            Watchpoint::Register(Register::A, 5),
+        ];
+
+        let mut changes = vec![];
+        while !mix.halted {
+            mix = mix.step();
+            changes.push(mix.changes.clone());
+        }
+
+        let change = |watchpoint, old, new| {
+            vec![Change {
+                watchpoint,
+                old,
+                new,
+            }]
+        };
+        assert_eq!(
+            changes,
+            vec![
+                change(mix.watchpoints[2], w(0, 0, 0, 0, 0), w(0, 0, 0, 0, 5)),
+                change(mix.watchpoints[0], w(0, 0, 0, 0, 0), w(0, 0, 0, 0, 5)),
+                vec![],
+                change(mix.watchpoints[1], w(0, 0, 0, 0, 0), w(0, 0, 0, 0, 5)),
+                vec![],
+                vec![],
+                change(mix.watchpoints[0], w(0, 0, 0, 0, 5), w(0, 0, 0, 0, 5)),
+                vec![],
+            ]
+        );
+    }
+
+    #[test]
+    fn register_operations_work_on_every_register() {
+        let operation = |code: u8, variant: u8| {
+            Operation::decode(Byte::new(code), Modification::from(Byte::new(variant))).unwrap()
+        };
+        for code in 48..=55 {
+            let register = Register::of_code(Byte::new(code));
+            let mut mix = Mix::default();
+            mix.memory[100] = w(0, 0, 0, 0, 7);
+
+            let mix = mix.exec(instruction(operation(code, 2), 5, None, None));
+            let mix = mix.exec(instruction(operation(code, 0), 3, None, None));
+            let mix = mix.exec(instruction(operation(code, 1), 1, None, None));
+            assert_eq!(mix.register(register).value(), 7, "{:?}", register);
+            let mix = mix.exec(instruction(operation(code + 8, 5), 100, None, None));
+            assert_eq!(mix.comparison_indicator, Comparison::Equal);
+            let mix = mix.exec(instruction(operation(code - 8, 2), 1000, None, None));
+            assert_eq!(mix.program_counter, 1000);
+            let mix = mix.exec(instruction(operation(code, 3), 7, None, None));
+            assert_eq!(mix.register(register).value(), -7, "{:?}", register);
+        }
+    }
+
+    #[test]
+    fn move_words() {
+        let mut mix = Mix::default();
+        mix.memory[1000] = w(1, 0, 0, 0, 0);
+        mix.memory[1001] = w(2, 0, 0, 0, 0);
+        mix.memory[1002] = w(3, 0, 0, 0, 0);
+        mix.i1 = Index::new(Plus, 15, 39);
+
+        let mix = mix.exec(instruction(MOVE, 1000, None, unit(3)));
+
+        assert_eq!(mix.memory[999], w(1, 0, 0, 0, 0));
+        assert_eq!(mix.memory[1000], w(2, 0, 0, 0, 0));
+        assert_eq!(mix.memory[1001], w(3, 0, 0, 0, 0));
+        assert_eq!(mix.memory[1002], w(3, 0, 0, 0, 0));
+        assert_eq!(mix.i1, Index::new(Plus, 15, 42));
+    }
+
+    #[test]
+    fn num_and_char() {
+        let mix = Mix {
+            a: -w(0, 0, 31, 32, 39),
+            x: w(37, 57, 47, 30, 30),
+            ..Mix::default()
+        };
+
+        let mix = mix.exec(instruction(NUM, 0, None, None));
+        assert_eq!(mix.a.value(), -12977700);
+        assert_eq!(mix.x, w(37, 57, 47, 30, 30));
+        let mix = mix.exec(instruction(INCA, 1, None, None));
+        let mix = mix.exec(instruction(CHAR, 0, None, None));
+        assert_eq!(mix.a, -w(30, 30, 31, 32, 39));
+        assert_eq!(mix.x, w(37, 37, 36, 39, 39));
+    }
+
+    #[test]
+    fn runs_program_until_halt() {
+        let printed = device::Lines::default();
+        let mut mix = Mix::default();
+        mix.attach(
+            device::CARD_READER,
+            device::CardReader::new(std::io::Cursor::new("ONE\nTWO\nTHREE\n")),
+        );
+        mix.attach(
+            device::PRINTER,
+            device::LinePrinter::new(printed.clone(), 60, device::Paging::FormFeed),
+        );
+        mix.load_image(&mix! {
+                  ENT1 3;
+            COPY: IN   100(16);
+                  OUT  100(18);
+                  DEC1 1;
+                  J1P  COPY;
+                  HLT;
+        });
+
+        let mix = mix.run();
+
+        assert!(mix.halted);
+        assert!(mix.fault.is_none());
+        assert_eq!(mix.program_counter, 6);
+        assert_eq!(printed.lines(), vec!["ONE", "TWO", "THREE"]);
+    }
+
+    #[test]
+    fn stops_on_fault() {
+        let mut mix = Mix::default();
+        load_program(
+            &mut mix,
+            &[
+                instruction(NOP, 0, None, None),
+                instruction(OUT, 100, None, unit(18)),
+                instruction(HLT, 0, None, None),
+            ],
+        );
+
+        let mix = mix.run();
+
+        assert!(!mix.halted);
+        assert!(matches!(mix.fault, Some(Fault::NoDevice(18))));
+        assert_eq!(mix.program_counter, 1, "points to the faulty instruction");
+
+        let mut mix = Mix::default();
+        mix.memory[0] = w(0, 0, 0, 3, 5);
+        let mix = mix.run();
+        assert!(matches!(mix.fault, Some(Fault::InvalidInstruction(0))));
+    }
+
+    #[test]
+    fn tape_round_trip() {
+        let tape = device::Words::default();
+        let mut mix = Mix::default();
+        mix.attach(0, device::Tape::new(tape.clone()));
+        mix.memory[1000] = w(1, 2, 3, 4, 5);
+        load_program(
+            &mut mix,
+            &[
+                instruction(OUT, 1000, None, unit(0)),
+                instruction(IOC, 0, None, unit(0)),
+                instruction(IN, 2000, None, unit(0)),
+                instruction(HLT, 0, None, None),
+            ],
+        );
+
+        let mix = mix.run();
+
+        assert!(mix.halted);
+        assert_eq!(tape.borrow().len(), 100);
+        assert_eq!(mix.memory[2000], w(1, 2, 3, 4, 5));
+    }
+
+    #[test]
+    fn execution_time() {
+        assert(instruction(LDA, 1000, None, None), 2);
+        assert(instruction(JMP, 1000, None, None), 1);
+        assert(instruction(MUL, 1000, None, None), 10);
+        assert(instruction(DIV, 1000, None, None), 12);
+        assert(instruction(HLT, 0, None, None), 10);
+        assert(instruction(MOVE, 1000, None, unit(3)), 7);
+        fn assert(instruction: Instruction, time: u64) {
+            let mix = Mix {
+                clock: 100,
+                ..Mix::default()
+            };
+
+            let mix = mix.exec(instruction);
+
+            assert_eq!(mix.clock, 100 + time, "{:?}", instruction.operation);
+        }
+    }
+
+    #[test]
+    fn device_stays_busy_for_transfer_time() {
+        let mut mix = Mix::default();
+        mix.attach_timed(0, device::Tape::new(device::Words::default()), 100);
+
+        let mix = mix.exec(instruction(OUT, 1000, None, unit(0)));
+        assert_eq!(mix.clock, 1);
+        let mix = mix.exec(instruction(JBUS, 2000, None, unit(0)));
+        assert_eq!(mix.program_counter, 2000, "busy right after OUT");
+        let mix = mix.exec(instruction(JRED, 3000, None, unit(0)));
+        assert_eq!(mix.program_counter, 2000, "not ready right after OUT");
+
+        let mut mix = mix;
+        mix.clock = 100;
+        let mix = mix.exec(instruction(JRED, 3000, None, unit(0)));
+        assert_eq!(mix.program_counter, 3000, "ready after transfer time");
+    }
+
+    #[test]
+    fn busy_device_blocks_next_operation() {
+        let mut mix = Mix::default();
+        mix.attach_timed(0, device::Tape::new(device::Words::default()), 100);
+
+        let mix = mix.exec(instruction(OUT, 1000, None, unit(0)));
+        let mix = mix.exec(instruction(OUT, 1000, None, unit(0)));
+        assert_eq!(mix.clock, 101, "waits for the first OUT");
+        let mix = mix.exec(instruction(IOC, 0, None, unit(0)));
+        assert_eq!(mix.clock, 201, "waits for the second OUT");
+    }
+
+    #[test]
+    fn buffering_overlaps_input_with_computation() {
+        let unbuffered = [
+            instruction(ENT1, 10, None, None),
+            instruction(IN, 1000, None, unit(16)),
+            instruction(JBUS, 2, None, unit(16)),
+            instruction(ENT2, 100, None, None),
+            instruction(DEC2, 1, None, None),
+            instruction(J2P, 4, None, None),
+            instruction(DEC1, 1, None, None),
+            instruction(J1P, 1, None, None),
+            instruction(HLT, 0, None, None),
+        ];
+        let buffered = [
+            instruction(ENT1, 10, None, None),
+            instruction(IN, 1000, None, unit(16)),
+            instruction(NOP, 0, None, None),
+            instruction(ENT2, 100, None, None),
+            instruction(DEC2, 1, None, None),
+            instruction(J2P, 4, None, None),
+            instruction(DEC1, 1, None, None),
+            instruction(J1P, 1, None, None),
+            instruction(HLT, 0, None, None),
+        ];
+
+        let unbuffered = run(&unbuffered);
+        let buffered = run(&buffered);
+
+        // every card takes 500u to read and 200u to process
+        assert!(unbuffered >= 10 * 700, "took {}u", unbuffered);
+        assert!(buffered < 10 * 500 + 300, "took {}u", buffered);
+        fn run(program: &[Instruction]) -> u64 {
+            let mut mix = Mix::default();
+            let deck = std::io::Cursor::new("CARD\n".repeat(10));
+            mix.attach_timed(16, device::CardReader::new(deck), 500);
+            load_program(&mut mix, program);
+
+            let mix = mix.run();
+
+            assert!(mix.halted);
+            mix.clock
+        }
+    }
+
+    const LOADING_ROUTINE: [&str; 2] = [
+        " O O6 Z O6    I C O4 0 EH A  F F CF 0  E   EU 0 IH G BB   EJ  CA. Z EU   EH E BA",
+        "   EU 2A-H S BB  C U 1AEH 2AEN V  E  CLU  ABG Z EH E BB J B. A  9",
+    ];
+
+    fn transfer_card(location: usize, words: &[Word]) -> String {
+        let mut card = format!("PROG {}{:04}", words.len(), location);
+        for word in words {
+            let digits = format!("{:010}", word.magnitude());
+            let last = digits.as_bytes()[9] - b'0';
+            let sign = if word.sign == Minus { 10 } else { 30 };
+            card.push_str(&digits[..9]);
+            card.push(Byte::new(sign + last).char());
+        }
+        card + "\n"
+    }
+
+    #[test]
+    fn go_reads_first_card() {
+        let mut mix = Mix::default();
+        let card = "    I".repeat(16);
+        mix.attach_timed(16, device::CardReader::new(std::io::Cursor::new(card)), 50);
+        mix.program_counter = 1000;
+        mix.halted = true;
+
+        let mix = mix.go();
+
+        assert!(mix.fault.is_none());
+        assert_eq!(mix.memory[..16], [w(0, 0, 0, 0, 9); 16]);
+        assert_eq!(mix.memory[16], w(0, 0, 0, 0, 0));
+        assert_eq!(mix.clock, 50, "waits for the read to finish");
+        assert_eq!(mix.program_counter, 0);
+        assert!(!mix.halted);
+
+        let mix = Mix::default().go();
+        assert!(matches!(mix.fault, Some(Fault::NoDevice(16))));
+    }
+
+    #[test]
+    fn go_bootstraps_deck_with_loading_routine() {
+        let program = [
+            Word::from(instruction(OUT, 2000, None, unit(18))),
+            Word::from(instruction(HLT, 0, None, None)),
+        ];
+        let data = [
+            -w(1, 2, 3, 4, 5),
+            w(8, 5, 13, 13, 16),
+            w(BYTE - 1, BYTE - 1, BYTE - 1, BYTE - 1, BYTE - 1),
+            -w(0, 0, 0, 0, 0),
+            -w(0, 0, 0, 0, 10),
+            w(0, 0, 0, 0, 0),
+            w(0, 0, 0, 0, 7),
+            -w(0, 0, 0, 0, 1),
+        ];
+        let deck = LOADING_ROUTINE.join("\n")
+            + "\n"
+            + &transfer_card(3000, &program)
+            + &transfer_card(1999, &data[..7])
+            + &transfer_card(2006, &data[7..])
+            + "TRANS03000\n";
+        let printed = device::Lines::default();
+        let mut mix = Mix::default();
+        mix.attach_timed(16, device::CardReader::new(std::io::Cursor::new(deck)), 100);
+        mix.attach(
+            18,
+            device::LinePrinter::new(printed.clone(), 60, device::Paging::FormFeed),
+        );
+
+        let mix = mix.go().run();
+
+        assert!(mix.halted, "{:?}", mix.fault);
+        assert_eq!(mix.memory[3000..3002], program);
+        assert_eq!(mix.memory[1999..2007], data);
+        assert_eq!(printed.lines(), vec!["HELLO?????         Δ         G    A"]);
+    }
 }
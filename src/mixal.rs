@@ -0,0 +1,821 @@
+//! Assembler for MIXAL, the symbolic language of MIX.
+
+pub mod cross_reference;
+pub mod deck;
+pub mod disassembler;
+pub mod expression;
+pub mod formatter;
+pub mod linker;
+pub mod listing;
+pub mod macros;
+pub mod mdk;
+pub mod navigation;
+pub mod object;
+pub mod preprocessor;
+
+use super::{
+    Address, Byte, Image, IndexNumber, Instruction, Modification, Operation, Word, BYTE,
+    MEMORY_SIZE, WORD_BYTES,
+};
+use expression::{local_symbol, Locals, Scope};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Largest magnitude that fits into a word.
+const WORD_MAX: i64 = (BYTE as i64).pow(WORD_BYTES as u32) - 1;
+
+/// Problem found in the source, lines are numbered from 1.
+#[derive(Debug, PartialEq)]
+pub struct Error {
+    /// Source file of the line, when the source was read from files.
+    pub file: Option<String>,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}: {}", file, self.line, self.message),
+            None => write!(f, "line {}: {}", self.line, self.message),
+        }
+    }
+}
+
+/// Assembled program with the values of its symbols.
+#[derive(Debug)]
+pub struct Program {
+    pub image: Image,
+    pub symbols: BTreeMap<String, i64>,
+    /// Location and word assembled from each source line that generates a word.
+    pub lines: BTreeMap<usize, (usize, Word)>,
+    /// Location and source text of every literal constant in the pool.
+    pub literals: Vec<(usize, String)>,
+}
+
+/// Assembles the source in two passes, the first one assigns locations and defines symbols,
+/// the second one generates words and may refer to symbols defined later.
+pub fn assemble(source: &str) -> Result<Program, Vec<Error>> {
+    let (assembler, image) = Assembler::default().run(source);
+    if assembler.errors.is_empty() {
+        let pool = assembler.pool as usize;
+        let literals = assembler
+            .literals
+            .into_iter()
+            .enumerate()
+            .map(|(i, (text, _))| (pool + i, format!("={}=", text)))
+            .collect();
+        Ok(Program {
+            image,
+            symbols: assembler.symbols,
+            lines: assembler.lines,
+            literals,
+        })
+    } else {
+        Err(assembler.errors)
+    }
+}
+
+/// LOC, OP and ADDRESS fields of a source line, the rest of the line is a comment.
+struct Statement {
+    line: usize,
+    location: String,
+    operation: String,
+    address: String,
+    /// Index of the literal constant in ADDRESS, assigned by the first pass.
+    literal: Option<usize>,
+}
+
+impl Statement {
+    /// Returns `None` for blank lines and comments that start with `*`.
+    ///
+    /// The operand of ALF is the five characters after the space that follows it,
+    /// or columns 17–21 when OP starts in column 12 as on Knuth's coding sheets.
+    fn parse(line: usize, text: &str) -> Option<Self> {
+        let chars: Vec<char> = text.trim_end().chars().collect();
+        if chars.is_empty() || chars[0] == '*' {
+            return None;
+        }
+        let mut position = 0;
+        let location = token(&chars, &mut position);
+        skip_spaces(&chars, &mut position);
+        let start = position;
+        let operation = token(&chars, &mut position);
+        let address = if operation == "ALF" {
+            let from = if start == 11 { 16 } else { position + 1 };
+            (from..from + WORD_BYTES as usize)
+                .map(|i| chars.get(i).copied().unwrap_or(' '))
+                .collect()
+        } else {
+            skip_spaces(&chars, &mut position);
+            token(&chars, &mut position)
+        };
+        Some(Statement {
+            line,
+            location,
+            operation,
+            address,
+            literal: None,
+        })
+    }
+}
+
+fn token(chars: &[char], position: &mut usize) -> String {
+    let start = *position;
+    while *position < chars.len() && !chars[*position].is_whitespace() {
+        *position += 1;
+    }
+    chars[start..*position].iter().collect()
+}
+
+fn skip_spaces(chars: &[char], position: &mut usize) {
+    while *position < chars.len() && chars[*position].is_whitespace() {
+        *position += 1;
+    }
+}
+
+#[derive(Default)]
+struct Assembler {
+    symbols: BTreeMap<String, i64>,
+    /// Lines that define each symbol, more than one when the symbol is defined again.
+    definitions: BTreeMap<String, Vec<usize>>,
+    locals: Locals,
+    /// Distinct literal constants with their values, in the order of their first use.
+    literals: Vec<(String, Word)>,
+    /// Location of the first literal constant, right before END.
+    pool: i64,
+    lines: BTreeMap<usize, (usize, Word)>,
+    /// Start location from the address of END.
+    start: Option<i64>,
+    /// Location of the first word, modules for the linker are assembled twice at different bases.
+    base: i64,
+    /// Whether ENTRY and EXTRN are allowed and END may leave out the start location.
+    relocatable: bool,
+    /// Symbols declared by ENTRY, with the lines that declare them.
+    entries: Vec<(usize, String)>,
+    /// Symbols declared by EXTRN, defined by other modules.
+    externals: BTreeSet<String>,
+    /// Locations of the instructions whose address is an external symbol.
+    imports: BTreeMap<usize, String>,
+    /// Lines of ORIG with the locations they set, which must move along with modules.
+    origins: Vec<(usize, i64)>,
+    errors: Vec<Error>,
+}
+
+impl Assembler {
+    fn run(mut self, source: &str) -> (Self, Image) {
+        let statements = source
+            .lines()
+            .enumerate()
+            .filter_map(|(number, text)| Statement::parse(number + 1, text))
+            .collect();
+        let placed = self.define(statements);
+        let image = self.generate(placed, source.lines().count());
+        (self, image)
+    }
+
+    /// First pass, returns statements that generate words together with their locations.
+    fn define(&mut self, statements: Vec<Statement>) -> Vec<(i64, Statement)> {
+        let mut location = self.base;
+        let mut placed = vec![];
+        for mut statement in statements {
+            let value = match statement.operation.as_str() {
+                "EQU" => self.evaluate_or_report(&statement, location),
+                "END" => location + self.literals.len() as i64,
+                _ => location,
+            };
+            if !statement.location.is_empty() {
+                self.define_symbol(&statement, value);
+            }
+            match statement.operation.as_str() {
+                "EQU" => {}
+                "ORIG" => {
+                    location = self.evaluate_or_report(&statement, location);
+                    self.origins.push((statement.line, location));
+                }
+                "END" => {
+                    self.pool = location;
+                    placed.push((value, statement));
+                    break;
+                }
+                "" => self.error(statement.line, "missing operation".to_string()),
+                "ENTRY" | "EXTRN" if self.relocatable => self.linkage(&statement),
+                "CON" | "ALF" => {
+                    placed.push((location, statement));
+                    location += 1;
+                }
+                mnemonic => {
+                    if Operation::from_mnemonic(mnemonic).is_none() {
+                        let message = format!("unknown operation {}", mnemonic);
+                        self.error(statement.line, message);
+                    }
+                    statement.literal = self.literal(&statement, location);
+                    placed.push((location, statement));
+                    location += 1;
+                }
+            }
+        }
+        placed
+    }
+
+    /// Second pass, fills the image with the words of the statements.
+    fn generate(&mut self, placed: Vec<(i64, Statement)>, lines: usize) -> Image {
+        let mut image = Image::default();
+        let mut end = false;
+        for (location, statement) in placed {
+            let word = match statement.operation.as_str() {
+                "END" => {
+                    end = true;
+                    for i in 0..self.literals.len() {
+                        let location = self.pool + i as i64;
+                        if in_memory(location) {
+                            image.words.insert(location as usize, self.literals[i].1);
+                        } else {
+                            let message = format!("literal at {} is outside of memory", location);
+                            self.error(statement.line, message);
+                        }
+                    }
+                    if self.relocatable && statement.address.is_empty() {
+                        continue;
+                    }
+                    match self
+                        .w_value(&statement.address, statement.line, location)
+                        .map(Word::value)
+                    {
+                        Ok(start) if in_memory(start) => {
+                            image.start = start as usize;
+                            self.start = Some(start);
+                        }
+                        Ok(start) => {
+                            let message = format!("start location {} is outside of memory", start);
+                            self.error(statement.line, message)
+                        }
+                        Err(message) => self.error(statement.line, message),
+                    }
+                    continue;
+                }
+                "CON" => self.w_value(&statement.address, statement.line, location),
+                "ALF" => alphabetic(&statement.address),
+                mnemonic => match Operation::from_mnemonic(mnemonic) {
+                    Some(operation) => self.instruction(operation, &statement, location),
+                    None => continue,
+                },
+            };
+            match word {
+                Ok(_) if !in_memory(location) => {
+                    let message = format!("location {} is outside of memory", location);
+                    self.error(statement.line, message)
+                }
+                Ok(word) => {
+                    image.words.insert(location as usize, word);
+                    self.lines.insert(statement.line, (location as usize, word));
+                    if let Some(symbol) = self.external(&statement) {
+                        self.imports.insert(location as usize, symbol.to_string());
+                    }
+                }
+                Err(message) => self.error(statement.line, message),
+            }
+        }
+        if !end {
+            self.error(lines, "missing END".to_string());
+        }
+        image
+    }
+
+    /// Defines the symbol in LOC, local symbols `dH` may be defined any number of times.
+    fn define_symbol(&mut self, statement: &Statement, value: i64) {
+        let symbol = &statement.location;
+        if let Some((digit, 'H')) = local_symbol(symbol) {
+            self.locals[digit].push((statement.line, value));
+            return;
+        }
+        if !is_symbol(symbol) {
+            self.error(statement.line, format!("invalid symbol {}", symbol));
+            return;
+        }
+        self.definitions
+            .entry(symbol.clone())
+            .or_default()
+            .push(statement.line);
+        if self.externals.contains(symbol) {
+            self.error(
+                statement.line,
+                format!("external symbol {} is defined", symbol),
+            );
+        } else if self.symbols.contains_key(symbol) {
+            self.error(
+                statement.line,
+                format!("symbol {} is already defined", symbol),
+            );
+        } else {
+            self.symbols.insert(symbol.clone(), value);
+        }
+    }
+
+    /// Declares the symbols of ENTRY or EXTRN, separated by commas.
+    fn linkage(&mut self, statement: &Statement) {
+        for symbol in statement.address.split(',') {
+            if !is_symbol(symbol) {
+                self.error(statement.line, format!("invalid symbol {}", symbol));
+            } else if statement.operation == "ENTRY" {
+                self.entries.push((statement.line, symbol.to_string()));
+            } else if self.symbols.contains_key(symbol) {
+                self.error(
+                    statement.line,
+                    format!("external symbol {} is defined", symbol),
+                );
+            } else {
+                self.externals.insert(symbol.to_string());
+            }
+        }
+    }
+
+    /// External symbol in the address part of the instruction, which must consist of it alone.
+    fn external<'a>(&self, statement: &'a Statement) -> Option<&'a str> {
+        let (a, _, _) = split_address(&statement.address).ok()?;
+        Some(a).filter(|a| self.externals.contains(*a))
+    }
+
+    /// Evaluates the literal constant of the instruction and adds it to the pool,
+    /// unless a literal with the same value is already there.
+    fn literal(&mut self, statement: &Statement, location: i64) -> Option<usize> {
+        let (text, _) = split_literal(&statement.address)?;
+        let evaluated = text.and_then(|text| {
+            let value = self.w_value(text, statement.line, location)?;
+            Ok((text, value))
+        });
+        let (text, value) = match evaluated {
+            Ok(evaluated) => evaluated,
+            Err(message) => {
+                self.error(statement.line, message);
+                return None;
+            }
+        };
+        let index = match self
+            .literals
+            .iter()
+            .position(|(_, literal)| *literal == value)
+        {
+            Some(index) => index,
+            None => {
+                self.literals.push((text.to_string(), value));
+                self.literals.len() - 1
+            }
+        };
+        Some(index)
+    }
+
+    /// Assembles ADDRESS of the form A,I(F), where every part is optional
+    /// and A may be a literal constant `=W=`.
+    fn instruction(
+        &self,
+        operation: Operation,
+        statement: &Statement,
+        location: i64,
+    ) -> Result<Word, String> {
+        let (literal, address) = match split_literal(&statement.address) {
+            Some((_, rest)) => (statement.literal, rest),
+            None => (None, statement.address.as_str()),
+        };
+        let (a, index, field) = split_address(address)?;
+        let a = if let Some(literal) = literal {
+            if !a.is_empty() {
+                return Err(format!("unexpected {} after literal constant", a));
+            }
+            self.pool + literal as i64
+        } else if a.is_empty() || self.externals.contains(a) {
+            0
+        } else {
+            self.expression(a, statement.line, location)?
+        };
+        if a.abs() >= BYTE as i64 * BYTE as i64 {
+            return Err(format!("address {} does not fit into two bytes", a));
+        }
+        let index = match index {
+            None => None,
+            Some(index) => match self.expression(index, statement.line, location)? {
+                i @ 0..=6 => IndexNumber::decode(Byte::new(i as u8)).flatten(),
+                i => return Err(format!("index {} is not between 0 and 6", i)),
+            },
+        };
+        let modification = match field {
+            None => None,
+            Some(field) => match self.expression(field, statement.line, location)? {
+                f if (0..BYTE as i64).contains(&f) => Some(Modification::from(Byte::new(f as u8))),
+                f => return Err(format!("field {} does not fit into a byte", f)),
+            },
+        };
+        Ok(Word::from(Instruction::new(
+            operation,
+            Address::new(a as i16),
+            index,
+            modification,
+        )))
+    }
+
+    fn expression(&self, text: &str, line: usize, location: i64) -> Result<i64, String> {
+        expression::expression(text, &self.scope(line, location))
+    }
+
+    fn w_value(&self, text: &str, line: usize, location: i64) -> Result<Word, String> {
+        expression::w_value(text, &self.scope(line, location))
+    }
+
+    fn scope(&self, line: usize, location: i64) -> Scope<'_> {
+        Scope {
+            symbols: &self.symbols,
+            locals: &self.locals,
+            line,
+            location,
+        }
+    }
+
+    /// Value of the W-value in ADDRESS, reports errors as zero.
+    fn evaluate_or_report(&mut self, statement: &Statement, location: i64) -> i64 {
+        self.w_value(&statement.address, statement.line, location)
+            .map(Word::value)
+            .unwrap_or_else(|message| {
+                self.error(statement.line, message);
+                0
+            })
+    }
+
+    fn error(&mut self, line: usize, message: String) {
+        self.errors.push(Error {
+            file: None,
+            line,
+            message,
+        });
+    }
+}
+
+/// Splits ADDRESS into the A, I and F parts.
+fn split_address(address: &str) -> Result<(&str, Option<&str>, Option<&str>), String> {
+    let (rest, field) = match address.find('(') {
+        Some(i) if address.ends_with(')') => {
+            (&address[..i], Some(&address[i + 1..address.len() - 1]))
+        }
+        Some(_) => return Err(format!("missing closing parenthesis in {}", address)),
+        None => (address, None),
+    };
+    Ok(match rest.find(',') {
+        Some(i) => (&rest[..i], Some(&rest[i + 1..]), field),
+        None => (rest, None, field),
+    })
+}
+
+fn is_symbol(symbol: &str) -> bool {
+    symbol.len() <= 10
+        && local_symbol(symbol).is_none()
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && symbol.chars().any(|c| c.is_ascii_uppercase())
+}
+
+/// Splits ADDRESS that starts with a literal constant `=W=` into the W-value and the rest.
+fn split_literal(address: &str) -> Option<(Result<&str, String>, &str)> {
+    let text = address.strip_prefix('=')?;
+    Some(match text.find('=') {
+        Some(end) => (Ok(&text[..end]), &text[end + 1..]),
+        None => (Err(format!("missing closing = in {}", address)), ""),
+    })
+}
+
+fn in_memory(location: i64) -> bool {
+    (0..MEMORY_SIZE as i64).contains(&location)
+}
+
+/// Word with the character codes of five characters.
+fn alphabetic(text: &str) -> Result<Word, String> {
+    let mut word = Word::default();
+    for (byte, c) in word.bytes.iter_mut().zip(text.chars()) {
+        *byte = Byte::from_char(c).ok_or_else(|| format!("{:?} is not a MIX character", c))?;
+    }
+    Ok(word)
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::device;
+    use crate::{IndexNumber::*, Mix, Operation::*, Sign};
+
+    fn instruction(
+        operation: Operation,
+        address: i16,
+        index: Option<IndexNumber>,
+        field: Option<(u8, u8)>,
+    ) -> Word {
+        Word::from(Instruction::new(
+            operation,
+            Address::new(address),
+            index,
+            field.map(|(l, r)| Modification::field(l, r)),
+        ))
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        assemble(source)
+            .unwrap_err()
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn assembles_instructions() {
+        let program = assemble(
+            " LDA 2000,1(1:5)
+ STJ 3000
+ IN 1000(16)
+ JMP 1
+ HLT
+ ENN1 -5,2
+ END 0",
+        )
+        .unwrap();
+
+        let words: Vec<Word> = program.image.words.values().copied().collect();
+        assert_eq!(
+            words,
+            vec![
+                instruction(LDA, 2000, Some(I1), Some((1, 5))),
+                instruction(STJ, 3000, None, Some((0, 2))),
+                instruction(IN, 1000, None, Some((2, 0))),
+                instruction(JMP, 1, None, None),
+                instruction(HLT, 0, None, None),
+                instruction(ENN1, -5, Some(I2), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn takes_default_fields_from_operations() {
+        for (operation, _, _, _) in crate::OPERATIONS.iter() {
+            let source = format!(" {} 100\n END 0", operation.mnemonic());
+
+            let program = assemble(&source).unwrap();
+
+            assert_eq!(
+                program.image.words[&0],
+                instruction(*operation, 100, None, None)
+            );
+        }
+    }
+
+    #[test]
+    fn assigns_locations_and_defines_symbols() {
+        let program = assemble(
+            "\
+* Comments and blank lines are skipped
+
+TEN        EQU  10
+           ORIG TEN
+FIRST      CON  -TEN-1
+SECOND     CON  1:5
+HERE       ORIG *+TEN
+TEXT       ALF  HELLO
+THERE      ALF   WORLD
+START      NOP  *
+LAST       END  START",
+        )
+        .unwrap();
+
+        assert_eq!(
+            program.symbols.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("FIRST".to_string(), 10),
+                ("HERE".to_string(), 12),
+                ("LAST".to_string(), 25),
+                ("SECOND".to_string(), 11),
+                ("START".to_string(), 24),
+                ("TEN".to_string(), 10),
+                ("TEXT".to_string(), 22),
+                ("THERE".to_string(), 23),
+            ]
+        );
+        assert_eq!(program.image.start, 24);
+        assert_eq!(
+            program.image.words.into_iter().collect::<Vec<_>>(),
+            vec![
+                (10, -Word::new(Sign::Plus, 0, 0, 0, 0, 11)),
+                (11, Word::new(Sign::Plus, 0, 0, 0, 0, 13)),
+                (22, Word::new(Sign::Plus, 8, 5, 13, 13, 16)),
+                (23, Word::new(Sign::Plus, 0, 26, 16, 19, 13)),
+                (24, instruction(NOP, 24, None, None)),
+            ]
+        );
+    }
+
+    #[test]
+    fn takes_w_values() {
+        let program = assemble(
+            "\
+SIZE       EQU  1(3:3),5(5:5)
+           ORIG SIZE-4100
+           CON  1,-1000(0:2)
+           ENTA SIZE/41
+           END  2(4:5)",
+        )
+        .unwrap();
+
+        assert_eq!(program.symbols["SIZE"], 64 * 64 + 5);
+        assert_eq!(program.image.start, 2);
+        assert_eq!(
+            program.image.words.into_iter().collect::<Vec<_>>(),
+            vec![
+                (1, Word::new(Sign::Minus, 15, 40, 0, 0, 1)),
+                (2, instruction(ENTA, 100, None, None)),
+            ]
+        );
+    }
+
+    #[test]
+    fn pools_literal_constants_before_end() {
+        let program = assemble(
+            "\
+TEN        EQU  10
+           ORIG 3000
+START      LDA  =1000=
+           ADD  =1000=,1
+           CMPA =1-2=(1:5)
+           STX  =1,-1000(0:2)=
+           ENTX =TEN*100=
+           JMP  *+1
+           HLT
+LAST       END  START",
+        )
+        .unwrap();
+
+        assert_eq!(program.symbols["LAST"], 3010);
+        assert_eq!(
+            program.image.words.into_iter().collect::<Vec<_>>(),
+            vec![
+                (3000, instruction(LDA, 3007, None, None)),
+                (3001, instruction(ADD, 3007, Some(I1), None)),
+                (3002, instruction(CMPA, 3008, None, Some((1, 5)))),
+                (3003, instruction(STX, 3009, None, None)),
+                (3004, instruction(ENTX, 3007, None, None)),
+                (3005, instruction(JMP, 3006, None, None)),
+                (3006, instruction(HLT, 0, None, None)),
+                (3007, Word::new(Sign::Plus, 0, 0, 0, 15, 40)),
+                (3008, Word::new(Sign::Minus, 0, 0, 0, 0, 1)),
+                (3009, Word::new(Sign::Minus, 15, 40, 0, 0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_local_symbols() {
+        let program = assemble(
+            "           ORIG 1000
+START      ENT1 3
+2H         DEC1 1
+           J1Z  2F
+           JMP  2B
+2H         JMP  3F
+3H         HLT
+2H         EQU  2B+10
+           CON  2B
+1H         CON  1F
+1H         CON  1B
+           END  START",
+        )
+        .unwrap();
+
+        assert_eq!(
+            program.image.words.into_iter().collect::<Vec<_>>(),
+            vec![
+                (1000, instruction(ENT1, 3, None, None)),
+                (1001, instruction(DEC1, 1, None, None)),
+                (1002, instruction(J1Z, 1004, None, None)),
+                (1003, instruction(JMP, 1001, None, None)),
+                (1004, instruction(JMP, 1005, None, None)),
+                (1005, instruction(HLT, 0, None, None)),
+                (1006, Word::new(Sign::Plus, 0, 0, 0, 15, 54)),
+                (1007, Word::new(Sign::Plus, 0, 0, 0, 15, 48)),
+                (1008, Word::new(Sign::Plus, 0, 0, 0, 15, 47)),
+            ]
+        );
+        assert_eq!(program.symbols.keys().collect::<Vec<_>>(), vec!["START"]);
+    }
+
+    #[test]
+    fn resolves_forward_references() {
+        let program = assemble(
+            "         ORIG 100
+START    JMP  LATER
+         NOP  LATER+1,2(NEXT)
+LATER    HLT
+NEXT     EQU  3:4
+         END  START",
+        )
+        .unwrap();
+
+        assert_eq!(program.image.words[&100], instruction(JMP, 102, None, None));
+        assert_eq!(
+            program.image.words[&101],
+            instruction(NOP, 103, Some(I2), Some((3, 4)))
+        );
+    }
+
+    #[test]
+    fn assembled_program_runs() {
+        let program = assemble(
+            "\
+PRINTER    EQU  18
+           ORIG 1000
+X          CON  5
+           CON  7
+           CON  -2
+           ORIG 3000
+START      ENT1 3
+LOOP       ADD  X-1,1
+           DEC1 1
+           J1P  LOOP
+           STA  SUM
+           OUT  MESSAGE(PRINTER)
+           HLT
+SUM        CON  0
+MESSAGE    ALF  HELLO
+           ALF   WORL
+           ALF  D
+           END  START",
+        )
+        .unwrap();
+        let printed = device::Lines::default();
+        let mut mix = Mix::default();
+        mix.attach(
+            18,
+            device::LinePrinter::new(printed.clone(), 60, device::Paging::FormFeed),
+        );
+        mix.load_image(&program.image);
+
+        let mix = mix.run();
+
+        assert!(mix.halted, "{:?}", mix.fault);
+        assert_eq!(mix.memory[program.symbols["SUM"] as usize].value(), 10);
+        assert_eq!(printed.lines(), vec!["HELLO WORLD"]);
+    }
+
+    #[test]
+    fn reports_errors_with_lines() {
+        assert_eq!(
+            errors(
+                "\
+X        EQU  Y
+X        NOP
+         LOAD 1000
+         LDA  UNKNOWN
+         LDA  4096
+         LDA  0,7
+         LDA  0(64)
+         LDA  0(1:5
+7        NOP
+         ORIG 3999
+         NOP
+         NOP
+         END  0"
+            ),
+            vec![
+                "line 1: undefined symbol Y",
+                "line 2: symbol X is already defined",
+                "line 3: unknown operation LOAD",
+                "line 9: invalid symbol 7",
+                "line 4: undefined symbol UNKNOWN",
+                "line 5: address 4096 does not fit into two bytes",
+                "line 6: index 7 is not between 0 and 6",
+                "line 7: field 64 does not fit into a byte",
+                "line 8: missing closing parenthesis in 0(1:5",
+                "line 12: location 4000 is outside of memory",
+            ]
+        );
+        assert_eq!(errors(" NOP"), vec!["line 1: missing END"]);
+        assert_eq!(
+            errors("2B NOP\n JMP 1B\n JMP 1F\n NOP 2H\n END 0"),
+            vec![
+                "line 1: invalid symbol 2B",
+                "line 2: 1B refers to no 1H before it",
+                "line 3: 1F refers to no 1H after it",
+                "line 4: undefined symbol 2H",
+            ]
+        );
+        assert_eq!(
+            errors(" LDA =LATER=\n LDA =1\n LDA =1=2\nLATER EQU 1\n END 0"),
+            vec![
+                "line 1: undefined symbol LATER",
+                "line 2: missing closing = in =1",
+                "line 3: unexpected 2 after literal constant",
+            ]
+        );
+        assert_eq!(
+            errors(" ORIG 3999\n LDA =1=\n END 0"),
+            vec!["line 3: literal at 4000 is outside of memory"]
+        );
+        assert_eq!(
+            errors(" CON 1(1:2),1073741823+1\n END 0"),
+            vec!["line 1: overflow in 1(1:2),1073741823+1: 1073741824 does not fit into a word"]
+        );
+    }
+}
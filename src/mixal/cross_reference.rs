@@ -0,0 +1,153 @@
+//! Cross-reference report of the symbols of a program.
+
+use super::expression::{local_symbol, references};
+use super::listing::name;
+use super::{Assembler, Statement};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Lists every symbol with its value, the lines that define it and the lines that use it,
+/// and notes symbols that are unused, undefined or defined more than once.
+///
+/// Lines are given by their `names`, see `line_numbers`. Local symbols are left out,
+/// since they are meant to be defined again and again.
+pub fn cross_reference(source: &str, names: &[String]) -> String {
+    let (assembler, _) = Assembler::default().run(source);
+    let mut uses: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, text) in source.lines().enumerate() {
+        let statement = match Statement::parse(i + 1, text) {
+            Some(statement) => statement,
+            None => continue,
+        };
+        if statement.operation != "ALF" {
+            for (_, symbol) in references(&statement.address) {
+                if local_symbol(&symbol).is_some() {
+                    continue;
+                }
+                let lines = uses.entry(symbol).or_default();
+                if lines.last() != Some(&statement.line) {
+                    lines.push(statement.line);
+                }
+            }
+        }
+        if statement.operation == "END" {
+            break;
+        }
+    }
+
+    let numbers = |lines: &[usize]| {
+        lines
+            .iter()
+            .map(|&line| name(names, line).trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let symbols: BTreeSet<&String> = assembler.definitions.keys().chain(uses.keys()).collect();
+    let rows: Vec<[String; 5]> = symbols
+        .into_iter()
+        .map(|symbol| {
+            let defined = assembler.definitions.get(symbol).map_or(&[][..], |d| d);
+            let used = uses.get(symbol).map_or(&[][..], |u| u);
+            let note = match (defined.len(), used.len()) {
+                (0, _) => "undefined",
+                (1, 0) => "unused",
+                (1, _) => "",
+                _ => "defined more than once",
+            };
+            [
+                symbol.clone(),
+                assembler
+                    .symbols
+                    .get(symbol)
+                    .map_or(String::new(), |value| value.to_string()),
+                if defined.is_empty() {
+                    "-".to_string()
+                } else {
+                    numbers(defined)
+                },
+                if used.is_empty() {
+                    "-".to_string()
+                } else {
+                    numbers(used)
+                },
+                note.to_string(),
+            ]
+        })
+        .collect();
+
+    let header = [
+        "SYMBOL".to_string(),
+        "VALUE".to_string(),
+        "DEFINED".to_string(),
+        "USED".to_string(),
+        String::new(),
+    ];
+    let defined = rows
+        .iter()
+        .chain(Some(&header))
+        .map(|row| row[2].len())
+        .max()
+        .unwrap_or_default();
+    let used = rows
+        .iter()
+        .chain(Some(&header))
+        .map(|row| row[3].len())
+        .max()
+        .unwrap_or_default();
+    Some(&header)
+        .into_iter()
+        .chain(&rows)
+        .map(|[symbol, value, defined_in, used_in, note]| {
+            let row = format!(
+                "{:<10} {:>10}  {:<defined$}  {:<used$}  {}",
+                symbol,
+                value,
+                defined_in,
+                used_in,
+                note,
+                defined = defined,
+                used = used
+            );
+            row.trim_end().to_string() + "\n"
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::mixal::listing::line_numbers;
+
+    #[test]
+    fn lists_definitions_values_and_uses() {
+        let source = "\
+PRINTER    EQU  18
+UNUSED     EQU  5
+           ORIG 3000
+START      IOC  0(PRINTER)
+X          LDA  X
+           OUT  BUF(PRINTER)
+X          HLT
+           JMP  MISSING
+TEXT       ALF  START
+2H         JMP  2B
+BUF        ORIG *+24
+           END  START
+           JMP  AFTER";
+
+        assert_eq!(
+            cross_reference(source, &line_numbers(1..=13))
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "SYMBOL          VALUE  DEFINED  USED",
+                "BUF              3007  11       06",
+                "MISSING                -        08     undefined",
+                "PRINTER            18  01       04 06",
+                "START            3000  04       12",
+                "TEXT             3005  09       -      unused",
+                "UNUSED              5  02       -      unused",
+                "X                3001  05 07    05     defined more than once",
+            ]
+        );
+    }
+}
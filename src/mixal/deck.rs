@@ -0,0 +1,169 @@
+//! Object deck of punched cards for the loading routine of Exercise 1.3.1–26.
+
+use crate::{Byte, Image, Sign, Word};
+
+/// Cards of the loading routine, the GO button reads the first one into locations 0–15.
+pub const LOADING_ROUTINE: [&str; 2] = [
+    " O O6 Z O6    I C O4 0 EH A  F F CF 0  E   EU 0 IH G BB   EJ  CA. Z EU   EH E BA",
+    "   EU 2A-H S BB  C U 1AEH 2AEN V  E  CLU  ABG Z EH E BB J B. A  9",
+];
+
+/// Locations below this one hold the loading routine and its buffer while the deck is read.
+pub const FIRST_LOCATION: usize = 45;
+
+/// Words on a transfer card, after the name, the count and the location.
+const WORDS_PER_CARD: usize = 7;
+
+/// Punches the loading routine, transfer cards with the words of the image
+/// and the final transfer card that jumps to the start location.
+pub fn deck(name: &str, image: &Image) -> Result<String, String> {
+    let name = card_name(name)?;
+    if let Some((&location, _)) = image.words.range(..FIRST_LOCATION).next() {
+        return Err(format!(
+            "location {} is used by the loading routine",
+            location
+        ));
+    }
+    let mut cards: Vec<String> = LOADING_ROUTINE
+        .iter()
+        .map(|card| card.to_string())
+        .collect();
+    let mut run: Vec<(usize, Word)> = vec![];
+    for (&location, &word) in &image.words {
+        let continues = run
+            .last()
+            .is_some_and(|&(last, _)| last + 1 == location && run.len() < WORDS_PER_CARD);
+        if !continues && !run.is_empty() {
+            cards.push(transfer_card(&name, &run));
+            run.clear();
+        }
+        run.push((location, word));
+    }
+    if !run.is_empty() {
+        cards.push(transfer_card(&name, &run));
+    }
+    cards.push(format!("TRANS0{:04}", image.start));
+    Ok(cards.iter().map(|card| format!("{}\n", card)).collect())
+}
+
+/// Name in columns 1–5, padded with spaces.
+fn card_name(name: &str) -> Result<String, String> {
+    if name.chars().count() > 5 || name.chars().any(|c| Byte::from_char(c).is_none()) {
+        return Err(format!(
+            "{:?} is not a name of at most 5 MIX characters",
+            name
+        ));
+    }
+    Ok(format!("{:<5}", name))
+}
+
+/// Card with the name, the number of words, the location of the first word
+/// and the words as ten digits, where the last digit of a negative word is overpunched.
+fn transfer_card(name: &str, run: &[(usize, Word)]) -> String {
+    let mut card = format!("{}{}{:04}", name, run.len(), run[0].0);
+    for (_, word) in run {
+        let digits = format!("{:010}", word.magnitude());
+        let last = digits.as_bytes()[9] - b'0';
+        let overpunch = if word.sign == Sign::Minus { 10 } else { 30 };
+        card.push_str(&digits[..9]);
+        card.push(Byte::new(overpunch + last).char());
+    }
+    card
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::device::{self, CardReader};
+    use crate::mixal::assemble;
+    use crate::Mix;
+    use std::io::Cursor;
+
+    #[test]
+    fn punches_transfer_cards() {
+        let mut image = Image {
+            start: 3000,
+            ..Image::default()
+        };
+        for (i, location) in (1000..1009).chain(2000..2002).enumerate() {
+            image
+                .words
+                .insert(location, Word::from_magnitude(Sign::Plus, i as u64));
+        }
+        image
+            .words
+            .insert(1001, -Word::from_magnitude(Sign::Plus, 1073741820));
+        image.words.insert(1002, -Word::default());
+
+        let deck = deck("PROG", &image).unwrap();
+
+        assert_eq!(
+            deck.lines().skip(2).collect::<Vec<_>>(),
+            vec![
+                "PROG 71000000000000010737418\
+                 2Δ000000000Δ0000000003000000000400000000050000000006",
+                "PROG 2100700000000070000000008",
+                "PROG 2200000000000090000000010",
+                "TRANS03000",
+            ]
+        );
+        assert_eq!(deck.lines().take(2).collect::<Vec<_>>(), LOADING_ROUTINE);
+    }
+
+    #[test]
+    fn rejects_what_the_loader_cannot_load() {
+        let mut image = Image::default();
+        image.words.insert(44, Word::default());
+        assert_eq!(
+            deck("PROG", &image),
+            Err("location 44 is used by the loading routine".to_string())
+        );
+        assert_eq!(
+            deck("PROGRAM", &Image::default()),
+            Err("\"PROGRAM\" is not a name of at most 5 MIX characters".to_string())
+        );
+        assert_eq!(
+            deck("prog", &Image::default()),
+            Err("\"prog\" is not a name of at most 5 MIX characters".to_string())
+        );
+    }
+
+    #[test]
+    fn deck_reproduces_image_after_go() {
+        let program = assemble(
+            "\
+PRINTER    EQU  18
+           ORIG 2000
+START      OUT  TEXT(PRINTER)
+           LDA  =-1000=
+           STA  1000
+           HLT
+TEXT       ALF  LOADE
+           ALF  D BY
+           ALF  CARDS
+           ORIG TEXT+24
+TABLE      CON  -1(0:2),2(3:3)
+           CON  *
+           CON  -0
+           END  START",
+        )
+        .unwrap();
+        let deck = deck("TEST", &program.image).unwrap();
+        let printed = device::Lines::default();
+        let mut mix = Mix::default();
+        mix.attach_timed(16, CardReader::new(Cursor::new(deck)), 100);
+        mix.attach(
+            18,
+            device::LinePrinter::new(printed.clone(), 60, device::Paging::FormFeed),
+        );
+
+        let mix = mix.go().run();
+
+        assert!(mix.halted, "{:?}", mix.fault);
+        for (&location, &word) in &program.image.words {
+            assert_eq!(mix.memory[location], word, "location {}", location);
+        }
+        assert_eq!(printed.lines(), vec!["LOADED BY CARDS"]);
+        assert_eq!(mix.memory[1000].value(), -1000);
+    }
+}
@@ -0,0 +1,192 @@
+//! Disassembler from words of memory back to MIXAL.
+
+use crate::{Byte, Instruction, Modification, Sign, Word};
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::ops::Range;
+
+/// Word as a MIXAL statement like `LDA 2000,1(1:5)`, or as `CON` when it is no instruction.
+///
+/// The field is left out when it is the default one of the operation.
+pub fn disassemble(word: Word) -> String {
+    match Instruction::decode(word) {
+        Some(instruction) => {
+            let (operation, address) = statement(instruction, |_| None);
+            format!("{} {}", operation, address).trim_end().to_string()
+        }
+        None => format!("CON {}", constant(word)),
+    }
+}
+
+/// Words of the range as MIXAL source that starts with ORIG, targets of jumps within
+/// the range get labels `L` followed by their location.
+pub fn disassemble_memory(memory: &[Word], range: Range<usize>) -> String {
+    let targets: BTreeSet<usize> = memory[range.clone()]
+        .iter()
+        .filter_map(|&word| Instruction::decode(word))
+        .filter(|instruction| is_jump(*instruction) && instruction.address.value() >= 0)
+        .map(|instruction| instruction.address.value() as usize)
+        .filter(|target| range.contains(target))
+        .collect();
+    let label = |location: usize| {
+        if targets.contains(&location) {
+            Some(format!("L{}", location))
+        } else {
+            None
+        }
+    };
+
+    let mut lines = vec![format!("{:<10} ORIG {}", "", range.start)];
+    for location in range.clone() {
+        let word = memory[location];
+        let (operation, address) = match Instruction::decode(word) {
+            Some(instruction) if is_jump(instruction) => statement(instruction, |address| {
+                usize::try_from(address).ok().and_then(label)
+            }),
+            Some(instruction) => statement(instruction, |_| None),
+            None => ("CON".to_string(), constant(word)),
+        };
+        let line = format!(
+            "{:<10} {:<4} {}",
+            label(location).unwrap_or_default(),
+            operation,
+            address
+        );
+        lines.push(line.trim_end().to_string());
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Mnemonic and ADDRESS of the instruction, `name` may give a symbol for the address.
+fn statement(instruction: Instruction, name: impl Fn(i16) -> Option<String>) -> (String, String) {
+    let operation = instruction.operation;
+    let value = instruction.address.value();
+    let mut address = match name(value) {
+        Some(symbol) => symbol,
+        None if value == 0 && instruction.address.sign == Sign::Minus => "-0".to_string(),
+        None => value.to_string(),
+    };
+    if let Some(index) = instruction.index {
+        address.push_str(&format!(",{}", index as u8));
+    }
+    let field = instruction.field();
+    if field != operation.default_modification() {
+        match field {
+            Modification::Field { l, r } if operation.uses_field() => {
+                address.push_str(&format!("({}:{})", l, r))
+            }
+            _ => address.push_str(&format!("({})", Byte::from(field).0)),
+        }
+    }
+    if address == "0" {
+        address.clear();
+    }
+    (operation.mnemonic(), address)
+}
+
+/// Jumps and the I/O operations that jump.
+fn is_jump(instruction: Instruction) -> bool {
+    matches!(instruction.operation.code().0, 34 | 38 | 39..=47)
+}
+
+fn constant(word: Word) -> String {
+    if word.value() == 0 && word.sign == Sign::Minus {
+        "-0".to_string()
+    } else {
+        word.value().to_string()
+    }
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::mixal::assemble;
+    use crate::MEMORY_SIZE;
+
+    #[test]
+    fn disassembles_instructions_and_constants() {
+        let words = |source: &str| {
+            let program = assemble(source).unwrap();
+            program
+                .image
+                .words
+                .values()
+                .map(|&word| disassemble(word))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            words(
+                "           LDA  2000,1(1:5)
+           LDA  2000
+           STZ  -5,6(0:0)
+           IN   1000(16)
+           MOVE 1000(3)
+           JMP  1000
+           JANP 1000,1
+           ENTA 5
+           HLT
+           SLAX 2
+           END  0"
+            ),
+            vec![
+                "LDA 2000,1(1:5)",
+                "LDA 2000",
+                "STZ -5,6(0:0)",
+                "IN 1000(16)",
+                "MOVE 1000(3)",
+                "JMP 1000",
+                "JANP 1000,1",
+                "ENTA 5",
+                "HLT",
+                "SLAX 2",
+            ]
+        );
+        assert_eq!(
+            disassemble(Word::new(Sign::Plus, 0, 0, 7, 0, 8)),
+            "CON 28680"
+        );
+        assert_eq!(disassemble(Word::new(Sign::Plus, 0, 0, 0, 7, 8)), "CON 456");
+        assert_eq!(disassemble(Word::new(Sign::Minus, 0, 0, 0, 0, 0)), "NOP -0");
+        assert_eq!(
+            disassemble(Word::new(Sign::Minus, 0, 0, 0, 63, 63)),
+            "CON -4095"
+        );
+    }
+
+    #[test]
+    fn disassembled_memory_assembles_to_the_same_words() {
+        let source = "           ORIG 3000
+START      ENT1 0
+LOOP       LDA  1000,1
+           JAZ  DONE
+           INC1 1
+           JMP  LOOP
+DONE       JMP  100
+           CON  -4095
+           END  START";
+        let program = assemble(source).unwrap();
+        let mut memory = vec![Word::default(); MEMORY_SIZE];
+        for (&location, &word) in &program.image.words {
+            memory[location] = word;
+        }
+
+        let text = disassemble_memory(&memory, 3000..3007);
+
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            vec![
+                "           ORIG 3000",
+                "           ENT1",
+                "L3001      LDA  1000,1",
+                "           JAZ  L3005",
+                "           INC1 1",
+                "           JMP  L3001",
+                "L3005      JMP  100",
+                "           CON  -4095",
+            ]
+        );
+        let again = assemble(&format!("{}           END  3000", text)).unwrap();
+        assert_eq!(again.image.words, program.image.words);
+    }
+}
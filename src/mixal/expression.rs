@@ -0,0 +1,454 @@
+//! Expressions and W-values, as described in Section 1.3.2.
+
+use super::WORD_MAX;
+use crate::{Byte, Modification, Sign, Word, BYTE, WORD_BYTES};
+use std::collections::BTreeMap;
+
+/// Definitions of the local symbols 0H–9H as lines and values, in the order of lines.
+pub type Locals = [Vec<(usize, i64)>; 10];
+
+/// Values of the symbols and of `*`, the location counter, at the line being assembled.
+pub struct Scope<'a> {
+    pub symbols: &'a BTreeMap<String, i64>,
+    pub locals: &'a Locals,
+    pub line: usize,
+    pub location: i64,
+}
+
+/// Digit and letter of a local symbol `dH`, `dB` or `dF`.
+pub fn local_symbol(symbol: &str) -> Option<(usize, char)> {
+    match symbol.as_bytes() {
+        &[digit @ b'0'..=b'9', letter @ (b'H' | b'B' | b'F')] => {
+            Some(((digit - b'0') as usize, letter as char))
+        }
+        _ => None,
+    }
+}
+
+/// Evaluates an expression strictly from left to right, without operator precedence.
+///
+/// Binary operators are `+`, `-`, `*`, `/`, `//` and `:`, where `a//b` is `a` times
+/// the word size divided by `b` and `a:b` is `8a + b`. Atoms are numbers, symbols and `*`.
+pub fn expression(text: &str, scope: &Scope) -> Result<i64, String> {
+    let mut parser = Parser {
+        text,
+        chars: text.chars().collect(),
+        position: 0,
+        scope,
+    };
+    let value = parser.expression()?;
+    match parser.peek() {
+        None => Ok(value),
+        Some(c) => Err(parser.unexpected(c)),
+    }
+}
+
+/// Evaluates a W-value `E1(F1),E2(F2),...` by storing every expression into its field
+/// of a zero word in turn, the field defaults to (0:5).
+pub fn w_value(text: &str, scope: &Scope) -> Result<Word, String> {
+    let mut parser = Parser {
+        text,
+        chars: text.chars().collect(),
+        position: 0,
+        scope,
+    };
+    let mut word = Word::default();
+    loop {
+        let value = to_word(parser.expression()?);
+        let field = match parser.peek() {
+            Some('(') => {
+                parser.position += 1;
+                let field = parser.expression()?;
+                parser.expect(')')?;
+                field_specification(field)?
+            }
+            _ => Modification::field(0, WORD_BYTES),
+        };
+        word = word.merge(value, field);
+        match parser.peek() {
+            None => return Ok(word),
+            Some(',') => parser.position += 1,
+            Some(c) => return Err(parser.unexpected(c)),
+        }
+    }
+}
+
+/// Symbols in the text of an ADDRESS, with the offsets of their first characters.
+pub fn references(text: &str) -> Vec<(usize, String)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut references = vec![];
+    let mut position = 0;
+    while position < chars.len() {
+        let start = position;
+        while position < chars.len() && chars[position].is_ascii_alphanumeric() {
+            position += 1;
+        }
+        let atom: String = chars[start..position].iter().collect();
+        if atom.chars().any(|c| !c.is_ascii_digit()) {
+            references.push((start, atom));
+        }
+        position = position.max(start + 1);
+    }
+    references
+}
+
+/// Word with the value, zero is positive.
+pub fn to_word(value: i64) -> Word {
+    let sign = if value < 0 { Sign::Minus } else { Sign::Plus };
+    Word::from_magnitude(sign, value.unsigned_abs())
+}
+
+/// Field specification (L:R) with 0 ≤ L ≤ R ≤ 5 encoded as 8L + R.
+pub fn field_specification(value: i64) -> Result<Modification, String> {
+    if !(0..BYTE as i64).contains(&value) {
+        return Err(format!("field {} does not fit into a byte", value));
+    }
+    let field = Modification::from(Byte::new(value as u8));
+    if !field.is_valid() {
+        return Err(format!(
+            "field ({}:{}) is not a field specification",
+            value / 8,
+            value % 8
+        ));
+    }
+    Ok(field)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    chars: Vec<char>,
+    position: usize,
+    scope: &'a Scope<'a>,
+}
+
+impl Parser<'_> {
+    fn expression(&mut self) -> Result<i64, String> {
+        let mut value = match self.peek() {
+            Some('+') => {
+                self.position += 1;
+                self.atom()?
+            }
+            Some('-') => {
+                self.position += 1;
+                -self.atom()?
+            }
+            _ => self.atom()?,
+        };
+        while let Some(operator) = self.operator() {
+            let operand = self.atom()?;
+            value = match operator {
+                "+" => value + operand,
+                "-" => value - operand,
+                "*" => value * operand,
+                "/" if operand == 0 => return Err(format!("division by zero in {}", self.text)),
+                "/" => value / operand,
+                "//" if operand == 0 => return Err(format!("division by zero in {}", self.text)),
+                "//" => value * (WORD_MAX + 1) / operand,
+                _ => 8 * value + operand,
+            };
+            if value.abs() > WORD_MAX {
+                return Err(format!(
+                    "overflow in {}: {} does not fit into a word",
+                    self.text, value
+                ));
+            }
+        }
+        Ok(value)
+    }
+
+    fn operator(&mut self) -> Option<&'static str> {
+        let operator = match (self.peek(), self.chars.get(self.position + 1)) {
+            (Some('/'), Some('/')) => "//",
+            (Some('+'), _) => "+",
+            (Some('-'), _) => "-",
+            (Some('*'), _) => "*",
+            (Some('/'), _) => "/",
+            (Some(':'), _) => ":",
+            _ => return None,
+        };
+        self.position += operator.len();
+        Some(operator)
+    }
+
+    fn atom(&mut self) -> Result<i64, String> {
+        if self.peek() == Some('*') {
+            self.position += 1;
+            return Ok(self.scope.location);
+        }
+        let start = self.position;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric()) {
+            self.position += 1;
+        }
+        let atom: String = self.chars[start..self.position].iter().collect();
+        if atom.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => self.unexpected(c),
+                None => format!("missing operand at the end of {}", self.text),
+            });
+        }
+        if atom.chars().all(|c| c.is_ascii_digit()) {
+            return match atom.parse() {
+                Ok(number) if atom.len() <= 10 && number <= WORD_MAX => Ok(number),
+                _ => Err(format!("number {} does not fit into a word", atom)),
+            };
+        }
+        match local_symbol(&atom) {
+            Some((digit, 'B')) => self.scope.locals[digit]
+                .iter()
+                .rev()
+                .find(|(line, _)| *line < self.scope.line)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| format!("{} refers to no {}H before it", atom, digit)),
+            Some((digit, 'F')) => self.scope.locals[digit]
+                .iter()
+                .find(|(line, _)| *line > self.scope.line)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| format!("{} refers to no {}H after it", atom, digit)),
+            _ => self
+                .scope
+                .symbols
+                .get(&atom)
+                .copied()
+                .ok_or_else(|| format!("undefined symbol {}", atom)),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.unexpected(c)),
+            None => Err(format!(
+                "missing {:?} at the end of {}",
+                expected, self.text
+            )),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn unexpected(&self, c: char) -> String {
+        format!("unexpected {:?} in {}", c, self.text)
+    }
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+
+    fn symbols() -> BTreeMap<String, i64> {
+        [("X", 1000), ("Y", -20), ("Z", 0), ("9Z", 7)]
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect()
+    }
+
+    fn locals() -> Locals {
+        let mut locals = Locals::default();
+        locals[2] = vec![(1, 100), (5, 200), (15, 300), (20, 400)];
+        locals[3] = vec![(10, 500)];
+        locals
+    }
+
+    fn evaluate(text: &str) -> Result<i64, String> {
+        let symbols = symbols();
+        expression(
+            text,
+            &Scope {
+                symbols: &symbols,
+                locals: &locals(),
+                line: 10,
+                location: 3000,
+            },
+        )
+    }
+
+    fn w(text: &str) -> Result<Word, String> {
+        let symbols = symbols();
+        w_value(
+            text,
+            &Scope {
+                symbols: &symbols,
+                locals: &locals(),
+                line: 10,
+                location: 3000,
+            },
+        )
+    }
+
+    #[test]
+    fn atomic_expressions() {
+        assert_eq!(evaluate("0"), Ok(0));
+        assert_eq!(evaluate("00052"), Ok(52));
+        assert_eq!(evaluate("1073741823"), Ok(1073741823));
+        assert_eq!(evaluate("X"), Ok(1000));
+        assert_eq!(evaluate("Y"), Ok(-20));
+        assert_eq!(evaluate("9Z"), Ok(7));
+        assert_eq!(evaluate("*"), Ok(3000));
+    }
+
+    #[test]
+    fn local_symbols() {
+        assert_eq!(evaluate("2B"), Ok(200));
+        assert_eq!(evaluate("2F"), Ok(300));
+        assert_eq!(evaluate("2F-2B"), Ok(100));
+        assert_eq!(
+            evaluate("3B"),
+            Err("3B refers to no 3H before it".to_string())
+        );
+        assert_eq!(
+            evaluate("3F"),
+            Err("3F refers to no 3H after it".to_string())
+        );
+        assert_eq!(evaluate("2H"), Err("undefined symbol 2H".to_string()));
+    }
+
+    #[test]
+    fn signed_expressions() {
+        assert_eq!(evaluate("+5"), Ok(5));
+        assert_eq!(evaluate("-5"), Ok(-5));
+        assert_eq!(evaluate("-Y"), Ok(20));
+        assert_eq!(evaluate("-*"), Ok(-3000));
+        assert_eq!(evaluate("+Z"), Ok(0));
+    }
+
+    #[test]
+    fn binary_operations() {
+        assert_eq!(evaluate("7+3"), Ok(10));
+        assert_eq!(evaluate("7-10"), Ok(-3));
+        assert_eq!(evaluate("7*3"), Ok(21));
+        assert_eq!(evaluate("7/3"), Ok(2));
+        assert_eq!(
+            evaluate("-7/3"),
+            Ok(-2),
+            "quotient is truncated towards zero"
+        );
+        assert_eq!(evaluate("1//3"), Ok(357913941));
+        assert_eq!(evaluate("1//2"), Ok(536870912));
+        assert_eq!(evaluate("1:5"), Ok(13));
+        assert_eq!(evaluate("0:0"), Ok(0));
+        assert_eq!(evaluate("X+Y"), Ok(980));
+    }
+
+    #[test]
+    fn evaluates_from_left_to_right() {
+        assert_eq!(evaluate("-1+5"), Ok(4));
+        assert_eq!(evaluate("-1+5*20/6"), Ok(13));
+        assert_eq!(evaluate("1//3*3"), Ok(1073741823));
+        assert_eq!(evaluate("*-3"), Ok(2997));
+        assert_eq!(evaluate("***"), Ok(9000000));
+        assert_eq!(evaluate("2+3*4"), Ok(20));
+        assert_eq!(evaluate("1+1:5"), Ok(21));
+        assert_eq!(evaluate("X-1*2"), Ok(1998));
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            evaluate("1073741823+1"),
+            Err("overflow in 1073741823+1: 1073741824 does not fit into a word".to_string())
+        );
+        assert_eq!(
+            evaluate("-1073741823-1"),
+            Err("overflow in -1073741823-1: -1073741824 does not fit into a word".to_string())
+        );
+        assert_eq!(
+            evaluate("32768*32768"),
+            Err("overflow in 32768*32768: 1073741824 does not fit into a word".to_string())
+        );
+        assert_eq!(
+            evaluate("1//1"),
+            Err("overflow in 1//1: 1073741824 does not fit into a word".to_string())
+        );
+        assert_eq!(
+            evaluate("1073741824"),
+            Err("number 1073741824 does not fit into a word".to_string())
+        );
+        assert_eq!(
+            evaluate("00000000001"),
+            Err("number 00000000001 does not fit into a word".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        assert_eq!(
+            evaluate(""),
+            Err("missing operand at the end of ".to_string())
+        );
+        assert_eq!(
+            evaluate("1+"),
+            Err("missing operand at the end of 1+".to_string())
+        );
+        assert_eq!(evaluate("1++2"), Err("unexpected '+' in 1++2".to_string()));
+        assert_eq!(evaluate("1,2"), Err("unexpected ',' in 1,2".to_string()));
+        assert_eq!(evaluate("7/-Y"), Err("unexpected '-' in 7/-Y".to_string()));
+        assert_eq!(evaluate("(1)"), Err("unexpected '(' in (1)".to_string()));
+        assert_eq!(evaluate("W"), Err("undefined symbol W".to_string()));
+        assert_eq!(evaluate("X/Z"), Err("division by zero in X/Z".to_string()));
+        assert_eq!(
+            evaluate("X//Z"),
+            Err("division by zero in X//Z".to_string())
+        );
+    }
+
+    #[test]
+    fn finds_references() {
+        assert_eq!(references(""), vec![]);
+        assert_eq!(references("1000,1(1:5)"), vec![]);
+        assert_eq!(
+            references("X+2B,I1(9Z)"),
+            vec![
+                (0, "X".to_string()),
+                (2, "2B".to_string()),
+                (5, "I1".to_string()),
+                (8, "9Z".to_string()),
+            ]
+        );
+        assert_eq!(references("=TEN*100="), vec![(1, "TEN".to_string())]);
+    }
+
+    #[test]
+    fn w_values() {
+        assert_eq!(w("1"), Ok(Word::new(Sign::Plus, 0, 0, 0, 0, 1)));
+        assert_eq!(w("-1000"), Ok(Word::new(Sign::Minus, 0, 0, 0, 15, 40)));
+        assert_eq!(
+            w("1,-1000(0:2)"),
+            Ok(Word::new(Sign::Minus, 15, 40, 0, 0, 1))
+        );
+        assert_eq!(w("-1000(0:2),1"), Ok(Word::new(Sign::Plus, 0, 0, 0, 0, 1)));
+        assert_eq!(
+            w("X(1:2),5(3:3),1:4(4:4),*(5:5)"),
+            Ok(Word::new(Sign::Plus, 15, 40, 5, 12, 56))
+        );
+        assert_eq!(w("Y(0:0)"), Ok(Word::new(Sign::Minus, 0, 0, 0, 0, 0)));
+        assert_eq!(w("1(4)"), Ok(Word::new(Sign::Plus, 0, 0, 0, 1, 0)));
+        assert_eq!(w("*+1(2:3)"), Ok(Word::new(Sign::Plus, 0, 46, 57, 0, 0)));
+    }
+
+    #[test]
+    fn invalid_w_values() {
+        assert_eq!(
+            w("1(6)"),
+            Err("field (0:6) is not a field specification".to_string())
+        );
+        assert_eq!(
+            w("1(2:1)"),
+            Err("field (2:1) is not a field specification".to_string())
+        );
+        assert_eq!(
+            w("1(64)"),
+            Err("field 64 does not fit into a byte".to_string())
+        );
+        assert_eq!(
+            w("1(1:5"),
+            Err("missing ')' at the end of 1(1:5".to_string())
+        );
+        assert_eq!(w("1,"), Err("missing operand at the end of 1,".to_string()));
+        assert_eq!(w("1(1:5)2"), Err("unexpected '2' in 1(1:5)2".to_string()));
+    }
+}
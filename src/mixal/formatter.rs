@@ -0,0 +1,163 @@
+//! Formatter that lays out MIXAL in the columns of Knuth's coding sheets.
+
+use crate::WORD_BYTES;
+
+/// Column of OP, counted from 0, as ADDRESS follows OP five columns later.
+const OPERATION: usize = 11;
+/// Column of the remarks after ADDRESS.
+const REMARKS: usize = 31;
+
+/// Rewrites every statement into LOC, OP, ADDRESS and remarks columns, removes spaces
+/// inside W-values and keeps comment lines as they are.
+pub fn format(source: &str) -> String {
+    source
+        .lines()
+        .map(|text| format_line(text) + "\n")
+        .collect()
+}
+
+/// Whether the source is laid out as the formatter would do it.
+pub fn is_formatted(source: &str) -> bool {
+    format(source) == source
+}
+
+fn format_line(text: &str) -> String {
+    if text.starts_with('*') || text.trim().is_empty() {
+        return text.trim_end().to_string();
+    }
+    let tokens = tokens(text);
+    let (location, rest) = if text.starts_with(char::is_whitespace) {
+        ("", &tokens[..])
+    } else {
+        (tokens[0].1, &tokens[1..])
+    };
+    let (at, operation, rest) = match rest.split_first() {
+        Some(((at, operation), rest)) => (*at, *operation, rest),
+        None => return location.to_string(),
+    };
+
+    let (address, remarks) = if operation == "ALF" {
+        alf(text, at)
+    } else {
+        let mut address = String::new();
+        let mut remarks = "";
+        for (start, token) in rest {
+            let continues = address.is_empty()
+                || address.ends_with(|c| ",(:+-*/=".contains(c))
+                || token.starts_with(|c| ",():+-*/=".contains(c));
+            if !continues {
+                remarks = text[*start..].trim_end();
+                break;
+            }
+            address.push_str(token);
+        }
+        (address, remarks)
+    };
+
+    let mut line = if location.len() < OPERATION {
+        format!("{:<10} {:<4} {}", location, operation, address)
+    } else {
+        format!("{} {} {}", location, operation, address)
+    };
+    if !remarks.is_empty() {
+        let line_end = line.trim_end().len();
+        line.truncate(line_end);
+        let padding = REMARKS.saturating_sub(line.chars().count()).max(1);
+        line.push_str(&" ".repeat(padding));
+        line.push_str(remarks);
+    }
+    line.trim_end().to_string()
+}
+
+/// Operand of ALF, the five characters that the assembler takes, and the remarks after it,
+/// `at` is the byte offset of OP.
+fn alf(text: &str, at: usize) -> (String, &str) {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let column = chars.iter().position(|(i, _)| *i == at).unwrap_or(0);
+    let from = if column == OPERATION {
+        OPERATION + 5
+    } else {
+        column + 4
+    };
+    let operand: String = (from..from + WORD_BYTES as usize)
+        .map(|i| chars.get(i).map_or(' ', |(_, c)| *c))
+        .collect();
+    let remarks = chars
+        .get(from + WORD_BYTES as usize)
+        .map_or("", |(i, _)| text[*i..].trim());
+    (operand, remarks)
+}
+
+/// Words of the line with their byte offsets.
+fn tokens(text: &str) -> Vec<(usize, &str)> {
+    let mut tokens = vec![];
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(from), true) => {
+                tokens.push((from, &text[from..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::mixal::assemble;
+
+    const MESSY: &str = "* PRINT A TABLE, COMMENTS STAY   AS THEY ARE
+PRINTER EQU 18    line printer
+\tORIG 3000
+START IOC 0 ( PRINTER )
+ LDA =-1= , 2   load one
+ ENT1 -2000 , 3 ( 1 : 5 )
+TITLE ALF TABLE  the title
+           ALF  A  B  remark
+VERYLONGLABEL NOP
+
+ HLT
+ END START";
+
+    #[test]
+    fn lays_out_statements_in_columns() {
+        assert_eq!(
+            format(MESSY).lines().collect::<Vec<_>>(),
+            vec![
+                "* PRINT A TABLE, COMMENTS STAY   AS THEY ARE",
+                "PRINTER    EQU  18             line printer",
+                "           ORIG 3000",
+                "START      IOC  0(PRINTER)",
+                "           LDA  =-1=,2         load one",
+                "           ENT1 -2000,3(1:5)",
+                "TITLE      ALF  TABLE          the title",
+                "           ALF  A  B           remark",
+                "VERYLONGLABEL NOP",
+                "",
+                "           HLT",
+                "           END  START",
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_alf_after_labels_that_contain_it() {
+        assert_eq!(format("HALF ALF ABCDE\n"), "HALF       ALF  ABCDE\n");
+        assert!(is_formatted("HALF       ALF  ABCDE\n"));
+        assert!(is_formatted("ALF        ALF  ALF A          text\n"));
+    }
+
+    #[test]
+    fn formatting_is_idempotent_and_keeps_the_program() {
+        let formatted = format(MESSY);
+
+        assert!(!is_formatted(MESSY));
+        assert!(is_formatted(&formatted));
+        let program = assemble(&formatted.replace("VERYLONGLABEL", "LONG")).unwrap();
+        assert_eq!(program.image.words.len(), 8);
+    }
+}
@@ -0,0 +1,228 @@
+//! Linker that places object modules one after another and resolves their symbols.
+
+use super::object::{address, with_address, Object};
+use crate::{Image, MEMORY_SIZE};
+use std::collections::BTreeMap;
+
+/// Places named modules consecutively from `origin`, relocates their addresses and
+/// replaces imported symbols by the locations that other modules export.
+///
+/// Exactly one module has to give the start location.
+pub fn link(modules: &[(&str, &Object)], origin: usize) -> Result<Image, Vec<String>> {
+    let mut errors = vec![];
+    let mut bases = vec![];
+    let mut base = origin;
+    for (name, object) in modules {
+        if base + object.size > MEMORY_SIZE {
+            errors.push(format!(
+                "module {} does not fit into memory at {}",
+                name, base
+            ));
+        }
+        bases.push(base);
+        base += object.size;
+    }
+
+    let mut exports: BTreeMap<&str, (&str, usize)> = BTreeMap::new();
+    for ((name, object), base) in modules.iter().zip(&bases) {
+        for (symbol, offset) in &object.exports {
+            match exports.get(symbol.as_str()) {
+                Some((other, _)) => errors.push(format!(
+                    "symbol {} is exported by {} and {}",
+                    symbol, other, name
+                )),
+                None => {
+                    exports.insert(symbol, (name, base + offset));
+                }
+            }
+        }
+    }
+
+    let mut image = Image::default();
+    let mut starts = vec![];
+    for ((name, object), &base) in modules.iter().zip(&bases) {
+        let mut unresolved = vec![];
+        for (&offset, &word) in &object.words {
+            let location = base + offset;
+            let mut word = word;
+            if object.relocations.contains(&offset) {
+                word = relocate(word, base as i64, name, location, &mut errors);
+            }
+            if let Some(symbol) = object.imports.get(&offset) {
+                match exports.get(symbol.as_str()) {
+                    Some(&(_, value)) => {
+                        word = relocate(word, value as i64, name, location, &mut errors)
+                    }
+                    None if !unresolved.contains(&symbol) => {
+                        unresolved.push(symbol);
+                        errors.push(format!("unresolved symbol {} in {}", symbol, name));
+                    }
+                    None => {}
+                }
+            }
+            image.words.insert(location, word);
+        }
+        if let Some(start) = object.start {
+            starts.push((name, base + start));
+        }
+    }
+
+    match starts[..] {
+        [(_, start)] => image.start = start,
+        [] => errors.push("no module gives the start location".to_string()),
+        _ => errors.push(format!(
+            "start location is given by {}",
+            starts
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect::<Vec<_>>()
+                .join(" and ")
+        )),
+    }
+    if errors.is_empty() {
+        Ok(image)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Adds the offset to the address of the word, which has to stay within two bytes.
+fn relocate(
+    word: crate::Word,
+    offset: i64,
+    name: &str,
+    location: usize,
+    errors: &mut Vec<String>,
+) -> crate::Word {
+    let relocated = address(word) + offset;
+    if relocated.abs() >= MEMORY_SIZE as i64 {
+        errors.push(format!(
+            "address {} at {} in {} does not fit into memory",
+            relocated, location, name
+        ));
+        return word;
+    }
+    with_address(word, relocated)
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::device::{self, LinePrinter};
+    use crate::mixal::object::assemble_object;
+    use crate::Mix;
+
+    const MAIN: &str = "           EXTRN PRINT,HELLO
+           ENTRY BACK
+START      ENT1 HELLO
+           JMP  PRINT
+BACK       HLT
+           END  START";
+
+    const PRINT: &str = "           EXTRN BACK
+           ENTRY PRINT,HELLO
+PRINT      OUT  0,1(18)
+           JBUS *(18)
+           JMP  BACK
+HELLO      ALF  HELLO
+           ALF   FROM
+           ALF   LINK
+           ALF  ED
+           ORIG HELLO+24
+           END";
+
+    #[test]
+    fn links_modules_into_a_program() {
+        let main = assemble_object(MAIN).unwrap();
+        let print = assemble_object(PRINT).unwrap();
+
+        let image = link(&[("MAIN", &main), ("PRINT", &print)], 3000).unwrap();
+
+        assert_eq!(image.start, 3000);
+        assert_eq!(address(image.words[&3000]), 3006);
+        assert_eq!(address(image.words[&3001]), 3003);
+        assert_eq!(address(image.words[&3004]), 3004);
+        assert_eq!(address(image.words[&3005]), 3002);
+
+        let printed = device::Lines::default();
+        let mut mix = Mix::default();
+        mix.attach(
+            18,
+            LinePrinter::new(printed.clone(), 60, device::Paging::FormFeed),
+        );
+        mix.load_image(&image);
+        let mix = mix.run();
+
+        assert!(mix.halted, "{:?}", mix.fault);
+        assert_eq!(printed.lines(), vec!["HELLO FROM LINKED"]);
+    }
+
+    #[test]
+    fn keeps_space_reserved_at_the_end_of_modules() {
+        let buffer = assemble_object(
+            "           ENTRY BUF
+BUF        ORIG *+24
+           END",
+        )
+        .unwrap();
+        let main = assemble_object(
+            "           EXTRN BUF
+START      OUT  BUF(18)
+           OUT  MSG(18)
+           HLT
+MSG        ALF  HELLO
+           ORIG MSG+24
+           END  START",
+        )
+        .unwrap();
+
+        assert_eq!(buffer.size, 24);
+        assert_eq!(buffer.to_string().parse::<Object>().map(|o| o.size), Ok(24));
+        let image = link(&[("BUFFER", &buffer), ("MAIN", &main)], 100).unwrap();
+
+        assert_eq!(image.start, 124);
+        assert_eq!(address(image.words[&124]), 100);
+        assert_eq!(address(image.words[&125]), 127);
+        assert_eq!(
+            link(&[("BUFFER", &buffer), ("MAIN", &main)], 3960),
+            Err(vec![
+                "module MAIN does not fit into memory at 3984".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_symbols_that_do_not_resolve() {
+        let main = assemble_object(MAIN).unwrap();
+        let print = assemble_object(PRINT).unwrap();
+
+        assert_eq!(
+            link(&[("MAIN", &main)], 0),
+            Err(vec![
+                "unresolved symbol HELLO in MAIN".to_string(),
+                "unresolved symbol PRINT in MAIN".to_string(),
+            ])
+        );
+        assert_eq!(
+            link(&[("PRINT", &print), ("AGAIN", &print)], 0),
+            Err(vec![
+                "symbol HELLO is exported by PRINT and AGAIN".to_string(),
+                "symbol PRINT is exported by PRINT and AGAIN".to_string(),
+                "unresolved symbol BACK in PRINT".to_string(),
+                "unresolved symbol BACK in AGAIN".to_string(),
+                "no module gives the start location".to_string(),
+            ])
+        );
+        assert_eq!(
+            link(
+                &[("MAIN", &main), ("PRINT", &print), ("MAIN2", &main)],
+                3970
+            ),
+            Err(vec![
+                "module MAIN2 does not fit into memory at 4000".to_string(),
+                "symbol BACK is exported by MAIN and MAIN2".to_string(),
+                "start location is given by MAIN and MAIN2".to_string(),
+            ])
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Listing of an assembled program in the two-column style of Section 1.3.2.
+
+use super::disassembler::disassemble;
+use super::macros::{Expansion, Kind};
+use super::{Program, Statement};
+use crate::{Sign, Word, BYTE};
+
+/// Lists every source line next to its location and assembled word, with the literal
+/// constants before END and the symbol table at the end. `names` are the names of the
+/// lines, see `line_numbers`.
+pub fn listing(source: &str, program: &Program, names: &[String]) -> String {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, text)| (format!("{} ", name(names, i + 1)), text, true));
+    rows(lines, program)
+}
+
+/// Line numbers of the same width, the names of lines that all come from one file.
+pub fn line_numbers(lines: impl IntoIterator<Item = usize>) -> Vec<String> {
+    let lines: Vec<usize> = lines.into_iter().collect();
+    let width = lines
+        .iter()
+        .max()
+        .map_or(0, |last| last.to_string().len())
+        .max(2);
+    lines
+        .iter()
+        .map(|line| format!("{:0width$}", line, width = width))
+        .collect()
+}
+
+/// Name of the line of the source, its number when it has no name.
+pub fn name(names: &[String], line: usize) -> String {
+    match line.checked_sub(1).and_then(|i| names.get(i)) {
+        Some(name) => name.clone(),
+        None => line.to_string(),
+    }
+}
+
+/// Lists the source of a program assembled with macros, lines generated by a call
+/// follow the call and are marked with `+` after the line number.
+pub fn macro_listing(expansion: &Expansion, program: &Program, names: &[String]) -> String {
+    let lines = expansion.lines.iter().map(|line| {
+        let mark = if line.kind == Kind::Expanded {
+            '+'
+        } else {
+            ' '
+        };
+        let number = format!("{}{}", name(names, line.number), mark);
+        let assembled = matches!(line.kind, Kind::Source | Kind::Expanded);
+        (number, line.text.as_str(), assembled)
+    });
+    rows(lines, program)
+}
+
+/// Rows for the numbered lines of the assembled source, together with whether they
+/// were assembled rather than left out as macro definitions or calls.
+fn rows<'a>(lines: impl Iterator<Item = (String, &'a str, bool)>, program: &Program) -> String {
+    let mut rows = vec![];
+    let mut end = false;
+    for (i, (number, text, assembled)) in lines.enumerate() {
+        let line = i + 1;
+        let statement = Statement::parse(line, text).filter(|_| assembled);
+        if !end && statement.is_some_and(|s| s.operation == "END") {
+            end = true;
+            for (location, literal) in &program.literals {
+                let code = code(*location, program.image.words[location]);
+                rows.push(format!(
+                    "{}  {:width$}  {}",
+                    code,
+                    "",
+                    literal,
+                    width = number.len() - 1
+                ));
+            }
+        }
+        let code = match program.lines.get(&line) {
+            Some(&(location, word)) => code(location, word),
+            None => " ".repeat(CODE),
+        };
+        rows.push(format!("{}  {} {}", code, number, text));
+    }
+    rows.push(String::new());
+    rows.push("SYMBOL TABLE".to_string());
+    for (symbol, value) in &program.symbols {
+        rows.push(format!("{:<10} {:>10}", symbol, value));
+    }
+    rows.iter()
+        .map(|row| row.trim_end().to_string() + "\n")
+        .collect()
+}
+
+/// Width of the column with locations and words.
+const CODE: usize = 21;
+
+fn code(location: usize, word: Word) -> String {
+    format!("{:04}  {}", location, instruction_form(word))
+}
+
+/// Word in the form `± AAAA I F C` of an instruction, fields as decimal numbers.
+pub fn instruction_form(word: Word) -> String {
+    let sign = if word.sign == Sign::Minus { '-' } else { '+' };
+    let address = word.bytes[0].0 as usize * BYTE as usize + word.bytes[1].0 as usize;
+    format!(
+        "{} {:04} {:02} {:02} {:02}",
+        sign, address, word.bytes[2].0, word.bytes[3].0, word.bytes[4].0
+    )
+}
+
+/// Line of a memory dump with the fields, value, characters and instruction of the word.
+pub fn dump_line(location: usize, word: Word) -> String {
+    let characters: String = word.bytes.iter().map(|byte| byte.char()).collect();
+    format!(
+        "{:04}  {}  {:>11}  {}  {}",
+        location,
+        instruction_form(word),
+        word.value(),
+        characters,
+        disassemble(word)
+    )
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::mixal::assemble;
+    use crate::mixal::macros::assemble_with_macros;
+
+    #[test]
+    fn lists_words_next_to_source() {
+        let source = "\
+* PRINT A TABLE
+PRINTER    EQU  18
+           ORIG 3000
+START      IOC  0(PRINTER)
+           LDA  =-1=
+           ENT1 -2000,3(1:5)
+
+TITLE      ALF  TABLE
+           CON  -1(0:2),2(3:3)
+           END  START";
+        let program = assemble(source).unwrap();
+
+        assert_eq!(
+            listing(source, &program, &line_numbers(1..=10))
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "                       01  * PRINT A TABLE",
+                "                       02  PRINTER    EQU  18",
+                "                       03             ORIG 3000",
+                "3000  + 0000 00 18 35  04  START      IOC  0(PRINTER)",
+                "3001  + 3005 00 05 08  05             LDA  =-1=",
+                "3002  - 2000 03 13 49  06             ENT1 -2000,3(1:5)",
+                "                       07",
+                "3003  + 1473 02 13 05  08  TITLE      ALF  TABLE",
+                "3004  - 0001 02 00 00  09             CON  -1(0:2),2(3:3)",
+                "3005  - 0000 00 00 01      =-1=",
+                "                       10             END  START",
+                "",
+                "SYMBOL TABLE",
+                "PRINTER            18",
+                "START            3000",
+                "TITLE            3003",
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_macro_expansions_after_calls() {
+        let source = "SKIP       MACRO N
+           JMP  *+N
+           ENDM
+           ORIG 100
+START      SKIP 2
+           LDA  =5=
+           HLT
+           END  START";
+        let (expansion, program) = assemble_with_macros(source).unwrap();
+
+        assert_eq!(
+            macro_listing(&expansion, &program, &line_numbers(1..=8))
+                .lines()
+                .take(10)
+                .collect::<Vec<_>>(),
+            vec![
+                "                       01  SKIP       MACRO N",
+                "                       02             JMP  *+N",
+                "                       03             ENDM",
+                "                       04             ORIG 100",
+                "                       05  START      SKIP 2",
+                "                       05+ START      EQU  *",
+                "0100  + 0102 00 00 39  05+            JMP  *+2",
+                "0101  + 0103 00 05 08  06             LDA  =5=",
+                "0102  + 0000 00 02 05  07             HLT",
+                "0103  + 0000 00 00 05      =5=",
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_words_as_instructions() {
+        assert_eq!(
+            instruction_form(Word::new(Sign::Plus, 31, 16, 2, 3, 8)),
+            "+ 2000 02 03 08"
+        );
+        assert_eq!(
+            instruction_form(Word::new(Sign::Minus, 63, 63, 63, 63, 63)),
+            "- 4095 63 63 63"
+        );
+    }
+}
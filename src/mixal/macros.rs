@@ -0,0 +1,542 @@
+//! Macros, an optional pre-pass that expands them into plain MIXAL.
+//!
+//! A definition names the macro in LOC and lists its parameters in ADDRESS:
+//!
+//! ```text
+//! PRINT      MACRO UNIT,TEXT
+//! WAIT       JBUS WAIT(UNIT)
+//!            OUT  TEXT(UNIT)
+//!            ENDM
+//! START      PRINT 18,BUF
+//! ```
+//!
+//! A call replaces parameters by its arguments and gives every label of the body a name
+//! of its own, so that a macro may be called many times. The name puts the number of
+//! the call in three base 36 digits before the label, which leaves seven characters for
+//! labels of bodies. Bodies may call other macros.
+
+use super::{assemble, is_symbol, Error, Program, Statement};
+use crate::Operation;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Digits of the numbers of calls in the names of labels.
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+/// Length of the number of the call in the names of labels.
+const PREFIX: u32 = 3;
+
+/// Pseudo-operations that a macro must not hide.
+const PSEUDO_OPERATIONS: [&str; 9] = [
+    "EQU", "ORIG", "CON", "ALF", "END", "ENTRY", "EXTRN", "MACRO", "ENDM",
+];
+
+/// Role of a line in the expansion.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Kind {
+    /// Line of the source that is assembled as it is.
+    Source,
+    /// Line of a macro definition, from MACRO to ENDM.
+    Definition,
+    /// Line of the source that calls a macro.
+    Call,
+    /// Line generated by a call.
+    Expanded,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Line {
+    /// Line of the source, for expanded lines the line of the outermost call.
+    pub number: usize,
+    pub text: String,
+    pub kind: Kind,
+}
+
+/// Source with macro calls followed by their expansions.
+#[derive(Debug, PartialEq, Default)]
+pub struct Expansion {
+    pub lines: Vec<Line>,
+}
+
+impl Expansion {
+    /// Source for the assembler with a line for every line of the expansion,
+    /// definitions and calls are left blank.
+    pub fn source(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| match line.kind {
+                Kind::Source | Kind::Expanded => format!("{}\n", line.text),
+                Kind::Definition | Kind::Call => "\n".to_string(),
+            })
+            .collect()
+    }
+
+    /// Moves errors from the lines of the expansion to the lines of the source.
+    fn original(&self, errors: Vec<Error>) -> Vec<Error> {
+        errors
+            .into_iter()
+            .map(|error| Error {
+                file: error.file,
+                line: error
+                    .line
+                    .checked_sub(1)
+                    .and_then(|i| self.lines.get(i))
+                    .map_or(error.line, |line| line.number),
+                message: error.message,
+            })
+            .collect()
+    }
+}
+
+/// Expands macros and assembles the result, errors refer to the lines of the source.
+pub fn assemble_with_macros(source: &str) -> Result<(Expansion, Program), Vec<Error>> {
+    let expansion = expand(source)?;
+    match assemble(&expansion.source()) {
+        Ok(program) => Ok((expansion, program)),
+        Err(errors) => Err(expansion.original(errors)),
+    }
+}
+
+#[derive(Clone)]
+struct Macro {
+    parameters: Vec<String>,
+    /// Symbols defined in LOC of the body, renamed by every call.
+    labels: BTreeSet<String>,
+    body: Vec<String>,
+}
+
+#[derive(Default)]
+struct Expander {
+    macros: BTreeMap<String, Macro>,
+    /// Number of calls so far, which makes labels of the bodies unique.
+    calls: usize,
+    lines: Vec<Line>,
+    errors: Vec<Error>,
+}
+
+/// Replaces macro definitions and calls by the statements of the bodies.
+pub fn expand(source: &str) -> Result<Expansion, Vec<Error>> {
+    let mut expander = Expander::default();
+    let mut definition: Option<(usize, String, Macro)> = None;
+    for (i, text) in source.lines().enumerate() {
+        let number = i + 1;
+        let statement = Statement::parse(number, text);
+        let operation = statement.as_ref().map(|s| s.operation.as_str());
+        if let Some((line, name, mut body)) = definition.take() {
+            expander.push(number, text, Kind::Definition);
+            match operation {
+                Some("ENDM") => expander.define(line, name, body),
+                Some("MACRO") => {
+                    let message = format!("macro definition inside {}", name);
+                    expander.error(number, message);
+                    definition = Some((line, name, body));
+                }
+                _ => {
+                    body.body.push(text.to_string());
+                    definition = Some((line, name, body));
+                }
+            }
+            continue;
+        }
+        match statement {
+            Some(statement) if statement.operation == "MACRO" => {
+                expander.push(number, text, Kind::Definition);
+                let parameters = expander.parameters(&statement);
+                let body = Macro {
+                    parameters,
+                    labels: BTreeSet::new(),
+                    body: vec![],
+                };
+                definition = Some((number, statement.location, body));
+            }
+            Some(statement) if statement.operation == "ENDM" => {
+                expander.push(number, text, Kind::Definition);
+                expander.error(number, "ENDM without MACRO".to_string());
+            }
+            Some(statement) if expander.macros.contains_key(&statement.operation) => {
+                expander.push(number, text, Kind::Call);
+                expander.call(number, &statement, &mut vec![]);
+            }
+            _ => expander.push(number, text, Kind::Source),
+        }
+    }
+    if let Some((line, name, _)) = definition {
+        expander.error(line, format!("missing ENDM for {}", name));
+    }
+    if expander.errors.is_empty() {
+        Ok(Expansion {
+            lines: expander.lines,
+        })
+    } else {
+        Err(expander.errors)
+    }
+}
+
+impl Expander {
+    fn parameters(&mut self, statement: &Statement) -> Vec<String> {
+        let mut parameters: Vec<String> = vec![];
+        for parameter in statement.address.split(',').filter(|p| !p.is_empty()) {
+            if !is_symbol(parameter) {
+                let message = format!("invalid parameter {}", parameter);
+                self.error(statement.line, message);
+            } else if parameters.iter().any(|p| p == parameter) {
+                let message = format!("parameter {} is repeated", parameter);
+                self.error(statement.line, message);
+            } else {
+                parameters.push(parameter.to_string());
+            }
+        }
+        parameters
+    }
+
+    fn define(&mut self, line: usize, name: String, mut definition: Macro) {
+        if !is_symbol(&name) {
+            self.error(line, format!("invalid macro name {:?}", name));
+            return;
+        }
+        if PSEUDO_OPERATIONS.contains(&name.as_str()) || Operation::from_mnemonic(&name).is_some() {
+            self.error(line, format!("macro {} hides an operation", name));
+            return;
+        }
+        if self.macros.contains_key(&name) {
+            self.error(line, format!("macro {} is already defined", name));
+            return;
+        }
+        definition.labels = definition
+            .body
+            .iter()
+            .filter_map(|text| Statement::parse(line, text))
+            .map(|statement| statement.location)
+            .filter(|label| is_symbol(label) && !definition.parameters.contains(label))
+            .collect();
+        let room = 10 - PREFIX as usize;
+        if let Some(label) = definition.labels.iter().find(|label| label.len() > room) {
+            let message = format!(
+                "label {} of macro {} is longer than {} characters",
+                label, name, room
+            );
+            self.error(line, message);
+            return;
+        }
+        self.macros.insert(name, definition);
+    }
+
+    /// Expands the call, `calling` holds the macros whose bodies contain it.
+    fn call(&mut self, number: usize, statement: &Statement, calling: &mut Vec<String>) {
+        let name = &statement.operation;
+        if calling.contains(name) {
+            self.error(number, format!("macro {} calls itself", name));
+            return;
+        }
+        let definition = self.macros[name].clone();
+        let arguments = split_arguments(&statement.address);
+        if arguments.len() > definition.parameters.len() {
+            let message = format!(
+                "macro {} takes {} arguments, not {}",
+                name,
+                definition.parameters.len(),
+                arguments.len()
+            );
+            self.error(number, message);
+            return;
+        }
+        self.calls += 1;
+        let prefix = match prefix(self.calls) {
+            Some(prefix) => prefix,
+            None => {
+                self.error(
+                    number,
+                    format!("too many macro calls to rename labels of {}", name),
+                );
+                return;
+            }
+        };
+        let mut names: BTreeMap<&str, String> = BTreeMap::new();
+        for (i, parameter) in definition.parameters.iter().enumerate() {
+            let argument = arguments.get(i).copied().unwrap_or_default();
+            names.insert(parameter, argument.to_string());
+        }
+        for label in &definition.labels {
+            names.insert(label, format!("{}{}", prefix, label));
+        }
+
+        if !statement.location.is_empty() {
+            let text = format!("{:<10} EQU  *", statement.location);
+            self.push(number, &text, Kind::Expanded);
+        }
+        calling.push(name.clone());
+        for text in &definition.body {
+            let line = match Statement::parse(number, text) {
+                Some(line) => line,
+                None => {
+                    self.push(number, text, Kind::Expanded);
+                    continue;
+                }
+            };
+            let operation = names
+                .get(line.operation.as_str())
+                .cloned()
+                .unwrap_or(line.operation);
+            let line = Statement {
+                location: substitute(&line.location, &names),
+                address: if operation == "ALF" {
+                    line.address
+                } else {
+                    substitute(&line.address, &names)
+                },
+                operation,
+                ..line
+            };
+            if self.macros.contains_key(&line.operation) {
+                self.call(number, &line, calling);
+            } else {
+                let text = format!(
+                    "{:<10} {:<4} {}",
+                    line.location, line.operation, line.address
+                );
+                self.push(number, text.trim_end(), Kind::Expanded);
+            }
+        }
+        calling.pop();
+    }
+
+    fn push(&mut self, number: usize, text: &str, kind: Kind) {
+        self.lines.push(Line {
+            number,
+            text: text.to_string(),
+            kind,
+        });
+    }
+
+    fn error(&mut self, line: usize, message: String) {
+        self.errors.push(Error {
+            file: None,
+            line,
+            message,
+        });
+    }
+}
+
+/// Splits arguments on commas that are not inside parentheses, so `A(1:2)` is one argument.
+fn split_arguments(address: &str) -> Vec<&str> {
+    if address.is_empty() {
+        return vec![];
+    }
+    let mut arguments = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in address.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                arguments.push(&address[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.push(&address[start..]);
+    arguments
+}
+
+/// Number of the call in base 36 that starts the names of its labels, `None` when it
+/// does not fit the prefix.
+fn prefix(call: usize) -> Option<String> {
+    let base = DIGITS.len();
+    if call >= base.pow(PREFIX) {
+        return None;
+    }
+    let digits = (0..PREFIX)
+        .rev()
+        .map(|i| DIGITS[call / base.pow(i) % base] as char);
+    Some(digits.collect())
+}
+
+/// Replaces whole symbols of the text that have new names.
+fn substitute(text: &str, names: &BTreeMap<&str, String>) -> String {
+    let mut result = String::new();
+    let mut symbol = String::new();
+    for c in text.chars().chain(Some(' ')) {
+        if c.is_ascii_alphanumeric() {
+            symbol.push(c);
+            continue;
+        }
+        match names.get(symbol.as_str()) {
+            Some(name) => result.push_str(name),
+            None => result.push_str(&symbol),
+        }
+        symbol.clear();
+        result.push(c);
+    }
+    result.pop();
+    result
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::device::{self, LinePrinter};
+    use crate::Mix;
+
+    const SOURCE: &str = "* PRINT TWO LINES
+PRINT      MACRO UNIT,TEXT
+WAIT       JBUS WAIT(UNIT)
+           OUT  TEXT(UNIT)
+           ENDM
+TWICE      MACRO TEXT
+           PRINT 18,TEXT
+           PRINT 18,TEXT
+           ENDM
+           ORIG 1000
+START      TWICE HELLO
+           HLT
+HELLO      ALF  HELLO
+           ORIG HELLO+24
+           END  START";
+
+    #[test]
+    fn expands_nested_calls_with_own_labels() {
+        let expansion = expand(SOURCE).unwrap();
+
+        assert_eq!(
+            expansion
+                .lines
+                .iter()
+                .filter(|line| line.kind == Kind::Expanded)
+                .map(|line| (line.number, line.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (11, "START      EQU  *"),
+                (11, "002WAIT    JBUS 002WAIT(18)"),
+                (11, "           OUT  HELLO(18)"),
+                (11, "003WAIT    JBUS 003WAIT(18)"),
+                (11, "           OUT  HELLO(18)"),
+            ]
+        );
+        assert_eq!(
+            expansion
+                .lines
+                .iter()
+                .map(|line| line.kind)
+                .filter(|kind| *kind != Kind::Expanded)
+                .collect::<Vec<_>>(),
+            [Kind::Source]
+                .iter()
+                .chain(&[Kind::Definition; 8])
+                .chain(&[Kind::Source, Kind::Call])
+                .chain(&[Kind::Source; 4])
+                .copied()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn expanded_program_runs() {
+        let (_, program) = assemble_with_macros(SOURCE).unwrap();
+        let printed = device::Lines::default();
+        let mut mix = Mix::default();
+        mix.attach(
+            18,
+            LinePrinter::new(printed.clone(), 60, device::Paging::FormFeed),
+        );
+        mix.load_image(&program.image);
+
+        let mix = mix.run();
+
+        assert!(mix.halted, "{:?}", mix.fault);
+        assert_eq!(printed.lines(), vec!["HELLO", "HELLO"]);
+    }
+
+    #[test]
+    fn substitutes_whole_symbols() {
+        let names = vec![("X", "1000".to_string()), ("F", "1:2".to_string())]
+            .into_iter()
+            .collect();
+
+        assert_eq!(substitute("X+X1,X(F)", &names), "1000+X1,1000(1:2)");
+        assert_eq!(split_arguments("A(1:2),,B"), vec!["A(1:2)", "", "B"]);
+        assert_eq!(split_arguments(""), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn reports_errors_with_source_lines() {
+        let errors = |source| {
+            assemble_with_macros(source)
+                .unwrap_err()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            errors(
+                "LDA        MACRO X,X
+           ENDM
+LOOP       MACRO
+           LOOP
+           ENDM
+           ENDM
+ONE        MACRO A
+           ENDM
+           ONE  1,2
+           LOOP
+OPEN       MACRO
+           END"
+            ),
+            vec![
+                "line 1: parameter X is repeated",
+                "line 1: macro LDA hides an operation",
+                "line 6: ENDM without MACRO",
+                "line 9: macro ONE takes 1 arguments, not 2",
+                "line 10: macro LOOP calls itself",
+                "line 11: missing ENDM for OPEN",
+            ]
+        );
+        assert_eq!(
+            errors(
+                "JUMP       MACRO
+           JMP  MISSING
+           ENDM
+           JUMP
+           END  0"
+            ),
+            vec!["line 4: undefined symbol MISSING"]
+        );
+        assert_eq!(
+            errors(
+                "WAIT       MACRO
+DEVICEBUSY JBUS DEVICEBUSY(18)
+           ENDM
+           END  0"
+            ),
+            vec!["line 1: label DEVICEBUSY of macro WAIT is longer than 7 characters"]
+        );
+    }
+
+    #[test]
+    fn renames_labels_of_many_calls_to_the_same_length() {
+        let mut source = "WAIT       MACRO
+PRINTER    JBUS PRINTER(18)
+           ENDM
+"
+        .to_string();
+        for _ in 0..40 {
+            source.push_str("           WAIT\n");
+        }
+        source.push_str("           HLT\n           END  0");
+
+        let (expansion, program) = assemble_with_macros(&source).unwrap();
+
+        let labels = expansion
+            .lines
+            .iter()
+            .filter(|line| line.kind == Kind::Expanded)
+            .map(|line| &line.text[..11])
+            .collect::<Vec<_>>();
+        assert_eq!(labels.len(), 40);
+        assert_eq!(labels[0], "001PRINTER ");
+        assert_eq!(labels[9], "00APRINTER ");
+        assert_eq!(labels[34], "00ZPRINTER ");
+        assert_eq!(labels[35], "010PRINTER ");
+        assert_eq!(program.image.words.len(), 41);
+    }
+}
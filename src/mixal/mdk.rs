@@ -0,0 +1,308 @@
+//! Compatibility with GNU MDK, its MIXAL dialect and its compiled programs.
+
+use super::{assemble, Error, Program, Statement};
+use crate::{Image, Operation, Sign, Word, MEMORY_SIZE};
+use std::convert::TryFrom;
+
+/// Pseudo-operations, which MDK also accepts in lowercase.
+const PSEUDO_OPERATIONS: [&str; 5] = ["EQU", "ORIG", "CON", "ALF", "END"];
+/// Operations that are written without ADDRESS, so that a remark may follow them directly.
+const WITHOUT_ADDRESS: [&str; 4] = ["HLT", "NOP", "NUM", "CHAR"];
+
+/// Assembles source written for MDK.
+pub fn assemble_mdk(source: &str) -> Result<Program, Vec<Error>> {
+    assemble(&normalize(source))
+}
+
+/// Rewrites the MDK dialect into standard MIXAL line by line, so that lines keep their numbers.
+///
+/// Lowercase operations become uppercase and `ALF "TEXT"` takes the quoted text,
+/// which may be shorter than five characters, as the operand in columns 17–21.
+/// After HLT, NOP, NUM and CHAR an ADDRESS with lowercase letters is a remark, as MIX
+/// has no lowercase characters. Other operations keep their ADDRESS, so that the assembler
+/// reports the symbols that MIXAL does not allow.
+pub fn normalize(source: &str) -> String {
+    source
+        .lines()
+        .map(|text| {
+            let line = match Statement::parse(0, text) {
+                Some(statement) => normalize_statement(text, statement),
+                None => text.to_string(),
+            };
+            line + "\n"
+        })
+        .collect()
+}
+
+fn normalize_statement(text: &str, statement: Statement) -> String {
+    let operation = statement.operation.to_ascii_uppercase();
+    let known = Operation::from_mnemonic(&operation).is_some()
+        || PSEUDO_OPERATIONS.contains(&operation.as_str());
+    if !known {
+        return text.to_string();
+    }
+    if operation == "ALF" {
+        if let Some(quoted) = quoted(text) {
+            return format!("{:<10} ALF  {}", statement.location, quoted);
+        }
+    }
+    // OP is the first occurrence of it after LOC.
+    let start = statement.location.len();
+    let at = start + text[start..].find(&statement.operation).unwrap_or(0);
+    let after = at + statement.operation.len();
+    let remark = WITHOUT_ADDRESS.contains(&operation.as_str())
+        && statement.address.chars().any(|c| c.is_ascii_lowercase());
+    let rest = if remark { "" } else { &text[after..] };
+    format!("{}{}{}", &text[..at], operation, rest)
+}
+
+/// Text between the quotes of an ALF operand.
+fn quoted(text: &str) -> Option<&str> {
+    let from = text.find('"')? + 1;
+    let to = from + text[from..].find('"')?;
+    Some(&text[from..to]).filter(|text| text.chars().count() <= 5)
+}
+
+/// First bytes of files compressed by gzip, as `mixasm` writes them when built with zlib.
+const GZIP: [u8; 2] = [0x1f, 0x8b];
+/// Signature of compiled programs, and of those that carry debug information.
+const SIGNATURE: u32 = 0xdead_beef;
+const DEBUG_SIGNATURE: u32 = 0xbeef_dead;
+/// Major version of the format.
+const VERSION: u32 = 1;
+/// Tag of the words in a compiled program that give the location of the next words.
+const LOCATION_TAG: u32 = 1 << 31;
+const SIGN_BIT: u32 = 1 << 30;
+const MAGNITUDE: u32 = SIGN_BIT - 1;
+/// Signature, major and minor versions, start location and length of the source path.
+const HEADER: usize = 24;
+
+/// Imports a program compiled by MDK's `mixasm` into an image.
+///
+/// The file is expected as `mixasm` writes it without compression and debug information:
+/// a header of the signature and the major and minor versions as 32-bit numbers, the 16-bit
+/// start location padded to 8 bytes and the 64-bit length of the path of the source, then
+/// the path and 32-bit words. Words tagged by bit 31 give the location of the next words,
+/// other words have the sign in bit 30 and five bytes in bits 0–29. All numbers are
+/// little-endian.
+pub fn import_mdk(bytes: &[u8]) -> Result<Image, String> {
+    if bytes.starts_with(&GZIP) {
+        return Err("file is compressed, decompress it with gunzip first".to_string());
+    }
+    if bytes.len() < HEADER {
+        return Err("file is too short for the header".to_string());
+    }
+    let number =
+        |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+    match number(0) {
+        SIGNATURE => {}
+        DEBUG_SIGNATURE => {
+            return Err("programs with debug information are not supported".to_string())
+        }
+        _ => return Err("file is not a program compiled by MDK".to_string()),
+    }
+    if number(4) != VERSION {
+        return Err(format!(
+            "version {}.{} is not supported",
+            number(4),
+            number(8)
+        ));
+    }
+    let start = u16::from_le_bytes([bytes[12], bytes[13]]) as usize;
+    let mut length = [0; 8];
+    length.copy_from_slice(&bytes[16..24]);
+    let code = usize::try_from(u64::from_le_bytes(length))
+        .ok()
+        .and_then(|path| HEADER.checked_add(path))
+        .and_then(|end| bytes.get(end..))
+        .ok_or("file is too short for the path of the source")?;
+    if code.len() % 4 != 0 {
+        return Err(format!("{} bytes do not make whole words", code.len()));
+    }
+    if start >= MEMORY_SIZE {
+        return Err(format!("start location {} is outside of memory", start));
+    }
+
+    let mut image = Image {
+        start,
+        ..Image::default()
+    };
+    let mut location = 0;
+    for chunk in code.chunks(4) {
+        let value = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        if value & LOCATION_TAG != 0 {
+            location = (value & MAGNITUDE) as usize;
+            continue;
+        }
+        if location >= MEMORY_SIZE {
+            return Err(format!("location {} is outside of memory", location));
+        }
+        let sign = if value & SIGN_BIT != 0 {
+            Sign::Minus
+        } else {
+            Sign::Plus
+        };
+        let word = Word::from_magnitude(sign, (value & MAGNITUDE) as u64);
+        image.words.insert(location, word);
+        location += 1;
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::device::{self, LinePrinter};
+    use crate::Mix;
+
+    #[test]
+    fn assembles_mdk_dialect() {
+        let source = "* HELLO WORLD FROM THE MDK MANUAL
+TERM    equ    18          the line printer
+        orig   1000        start address
+START   out    MSG(TERM)   output data at address MSG
+        hlt                halt execution
+MSG     alf    \"MIXAL\"
+        ALF    \" HELL\"
+        alf    \"O WOR\"
+        ALF    \"LD\"
+        end    START       end of the program";
+
+        assert_eq!(
+            normalize(source).lines().skip(1).collect::<Vec<_>>(),
+            vec![
+                "TERM    EQU    18          the line printer",
+                "        ORIG   1000        start address",
+                "START   OUT    MSG(TERM)   output data at address MSG",
+                "        HLT",
+                "MSG        ALF  MIXAL",
+                "           ALF   HELL",
+                "           ALF  O WOR",
+                "           ALF  LD",
+                "        END    START       end of the program",
+            ]
+        );
+
+        let program = assemble_mdk(source).unwrap();
+        let printed = device::Lines::default();
+        let mut mix = Mix::default();
+        mix.attach(
+            18,
+            LinePrinter::new(printed.clone(), 60, device::Paging::FormFeed),
+        );
+        mix.load_image(&program.image);
+        let mix = mix.run();
+
+        assert!(mix.halted, "{:?}", mix.fault);
+        assert_eq!(printed.lines(), vec!["MIXAL HELLO WORLD"]);
+    }
+
+    #[test]
+    fn leaves_unknown_operations_and_symbols_alone() {
+        assert_eq!(normalize("ab      cd   ef\n"), "ab      cd   ef\n");
+        assert_eq!(normalize("lda     lda  1000\n"), "lda     LDA  1000\n");
+        assert_eq!(normalize("        jmp  start\n"), "        JMP  start\n");
+        assert_eq!(normalize("        num  to numbers\n"), "        NUM\n");
+        let errors = assemble_mdk("        jmp  start\n        end  0\n").err();
+        assert_eq!(
+            errors.map(|errors| errors.into_iter().map(|error| error.message).collect()),
+            Some(vec!["undefined symbol start".to_string()])
+        );
+        assert_eq!(
+            normalize("ALF     ALF  \"TOO LONG\"\n"),
+            "ALF     ALF  \"TOO LONG\"\n"
+        );
+    }
+
+    fn compiled(start: u16, path: &str, words: &[u32]) -> Vec<u8> {
+        let mut bytes = vec![];
+        for number in &[SIGNATURE, 1, 3] {
+            bytes.extend_from_slice(&number.to_le_bytes());
+        }
+        bytes.extend_from_slice(&start.to_le_bytes());
+        bytes.extend_from_slice(&[0; 2]);
+        bytes.extend_from_slice(&(path.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(path.as_bytes());
+        for word in words {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn imports_compiled_programs() {
+        let bytes = compiled(
+            3000,
+            "hello.mixal",
+            &[
+                LOCATION_TAG | 3000,
+                (1 << 18) | (2 << 6) | 5,
+                SIGN_BIT | 7,
+                LOCATION_TAG | 100,
+                42,
+            ],
+        );
+
+        let image = import_mdk(&bytes).unwrap();
+
+        assert_eq!(image.start, 3000);
+        assert_eq!(
+            image.words.into_iter().collect::<Vec<_>>(),
+            vec![
+                (100, Word::new(Sign::Plus, 0, 0, 0, 0, 42)),
+                (3000, Word::new(Sign::Plus, 0, 1, 0, 2, 5)),
+                (3001, Word::new(Sign::Minus, 0, 0, 0, 0, 7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_compiled_programs() {
+        assert_eq!(
+            import_mdk(&[0; 10]),
+            Err("file is too short for the header".to_string())
+        );
+        assert_eq!(
+            import_mdk(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3]),
+            Err("file is compressed, decompress it with gunzip first".to_string())
+        );
+        assert_eq!(
+            import_mdk(&[0; HEADER]),
+            Err("file is not a program compiled by MDK".to_string())
+        );
+        let mut debug = compiled(0, "", &[]);
+        debug[..4].copy_from_slice(&DEBUG_SIGNATURE.to_le_bytes());
+        assert_eq!(
+            import_mdk(&debug),
+            Err("programs with debug information are not supported".to_string())
+        );
+        let mut version = compiled(0, "", &[]);
+        version[4] = 2;
+        assert_eq!(
+            import_mdk(&version),
+            Err("version 2.3 is not supported".to_string())
+        );
+        let mut path = compiled(0, "", &[]);
+        path[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            import_mdk(&path),
+            Err("file is too short for the path of the source".to_string())
+        );
+        assert_eq!(
+            import_mdk(&compiled(0, "x", &[])[..HEADER]),
+            Err("file is too short for the path of the source".to_string())
+        );
+        assert_eq!(
+            import_mdk(&compiled(0, "", &[1])[..HEADER + 3]),
+            Err("3 bytes do not make whole words".to_string())
+        );
+        assert_eq!(
+            import_mdk(&compiled(4000, "", &[])),
+            Err("start location 4000 is outside of memory".to_string())
+        );
+        assert_eq!(
+            import_mdk(&compiled(0, "", &[LOCATION_TAG | 3999, 1, 2])),
+            Err("location 4000 is outside of memory".to_string())
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Positions of symbols in the source, for editors that jump between definitions and uses.
+
+use super::expression::{local_symbol, references};
+use super::Statement;
+use crate::Operation;
+
+/// Symbol in LOC that defines it or in ADDRESS that uses it.
+///
+/// Lines and characters are counted from 0, `end` is the character after the symbol.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Occurrence {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub name: String,
+    pub definition: bool,
+}
+
+impl Occurrence {
+    /// Whether it is a local symbol `dH`, `dB` or `dF`.
+    pub fn is_local(&self) -> bool {
+        local_symbol(&self.name).is_some()
+    }
+
+    fn contains(&self, line: usize, character: usize) -> bool {
+        self.line == line && self.start <= character && character <= self.end
+    }
+}
+
+/// Fields of a statement with the characters where they start.
+struct Fields {
+    location: String,
+    operation: (usize, String),
+    address: (usize, String),
+}
+
+fn fields(text: &str) -> Option<Fields> {
+    let statement = Statement::parse(0, text)?;
+    let chars: Vec<char> = text.chars().collect();
+    let mut position = statement.location.chars().count();
+    while position < chars.len() && chars[position].is_whitespace() {
+        position += 1;
+    }
+    let operation = position;
+    position += statement.operation.chars().count();
+    while position < chars.len() && chars[position].is_whitespace() {
+        position += 1;
+    }
+    Some(Fields {
+        location: statement.location,
+        operation: (operation, statement.operation),
+        address: (position, statement.address),
+    })
+}
+
+/// Definitions and uses of every symbol, local symbols included, in the order of the source.
+pub fn occurrences(source: &str) -> Vec<Occurrence> {
+    let mut occurrences = vec![];
+    for (line, text) in source.lines().enumerate() {
+        let fields = match fields(text) {
+            Some(fields) => fields,
+            None => continue,
+        };
+        if !fields.location.is_empty() {
+            occurrences.push(Occurrence {
+                line,
+                start: 0,
+                end: fields.location.chars().count(),
+                name: fields.location,
+                definition: true,
+            });
+        }
+        let (operation, address) = (fields.operation.1, fields.address);
+        if operation == "ALF" {
+            continue;
+        }
+        for (offset, name) in references(&address.1) {
+            let start = address.0 + offset;
+            occurrences.push(Occurrence {
+                line,
+                start,
+                end: start + name.len(),
+                name,
+                definition: false,
+            });
+        }
+        if operation == "END" {
+            break;
+        }
+    }
+    occurrences
+}
+
+/// Occurrence at the position.
+pub fn occurrence_at(
+    occurrences: &[Occurrence],
+    line: usize,
+    character: usize,
+) -> Option<&Occurrence> {
+    occurrences.iter().find(|o| o.contains(line, character))
+}
+
+/// Definition that the occurrence refers to: the nearest `dH` before `dB` or after `dF`,
+/// or the first definition of other symbols.
+pub fn definition<'a>(
+    occurrences: &'a [Occurrence],
+    occurrence: &Occurrence,
+) -> Option<&'a Occurrence> {
+    let mut definitions = occurrences.iter().filter(|o| o.definition);
+    match local_symbol(&occurrence.name) {
+        Some((digit, 'B')) => definitions
+            .rev()
+            .find(|o| local_symbol(&o.name) == Some((digit, 'H')) && o.line < occurrence.line),
+        Some((digit, 'F')) => definitions
+            .find(|o| local_symbol(&o.name) == Some((digit, 'H')) && o.line > occurrence.line),
+        Some(_) => definitions.find(|o| o.line == occurrence.line && o.name == occurrence.name),
+        None => definitions.find(|o| o.name == occurrence.name),
+    }
+}
+
+/// Uses of the symbol that the occurrence refers to, after its definition when asked for.
+pub fn references_to<'a>(
+    occurrences: &'a [Occurrence],
+    occurrence: &Occurrence,
+    declaration: bool,
+) -> Vec<&'a Occurrence> {
+    let target = definition(occurrences, occurrence);
+    occurrences
+        .iter()
+        .filter(|o| {
+            if o.definition {
+                declaration && Some(*o) == target
+            } else {
+                match (target, definition(occurrences, o)) {
+                    (Some(target), Some(other)) => target == other,
+                    (None, None) => o.name == occurrence.name,
+                    _ => false,
+                }
+            }
+        })
+        .collect()
+}
+
+/// Operation in OP at the position.
+pub fn operation_at(source: &str, line: usize, character: usize) -> Option<Operation> {
+    let fields = fields(source.lines().nth(line)?)?;
+    let (start, operation) = fields.operation;
+    if (start..=start + operation.len()).contains(&character) {
+        Operation::from_mnemonic(&operation)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+
+    const SOURCE: &str = "* LOOP OVER A TABLE
+N          EQU  10
+START      ENT1 N
+2H         LDA  TABLE,1
+           JANZ 2F
+           DEC1 1
+           J1P  2B
+2H         JMP  START
+TEXT       ALF  START
+TABLE      CON  N*2
+           END  START";
+
+    fn position(occurrence: Option<&Occurrence>) -> Option<(usize, usize)> {
+        occurrence.map(|o| (o.line, o.start))
+    }
+
+    #[test]
+    fn finds_definitions_and_uses() {
+        let occurrences = occurrences(SOURCE);
+
+        assert_eq!(
+            occurrences
+                .iter()
+                .map(|o| (o.line, o.start, o.name.as_str(), o.definition))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, 0, "N", true),
+                (2, 0, "START", true),
+                (2, 16, "N", false),
+                (3, 0, "2H", true),
+                (3, 16, "TABLE", false),
+                (4, 16, "2F", false),
+                (6, 16, "2B", false),
+                (7, 0, "2H", true),
+                (7, 16, "START", false),
+                (8, 0, "TEXT", true),
+                (9, 0, "TABLE", true),
+                (9, 16, "N", false),
+                (10, 16, "START", false),
+            ]
+        );
+        let at = |line, character| occurrence_at(&occurrences, line, character).unwrap();
+        assert_eq!(position(definition(&occurrences, at(3, 18))), Some((9, 0)));
+        assert_eq!(position(definition(&occurrences, at(4, 17))), Some((7, 0)));
+        assert_eq!(position(definition(&occurrences, at(6, 16))), Some((3, 0)));
+        assert_eq!(position(definition(&occurrences, at(7, 1))), Some((7, 0)));
+        assert_eq!(occurrence_at(&occurrences, 3, 12), None);
+    }
+
+    #[test]
+    fn finds_references_of_symbols_and_local_labels() {
+        let occurrences = occurrences(SOURCE);
+        let at = |line, character| occurrence_at(&occurrences, line, character).unwrap();
+        let references = |o, declaration| {
+            references_to(&occurrences, o, declaration)
+                .into_iter()
+                .map(|o| (o.line, o.start))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(references(at(1, 0), true), vec![(1, 0), (2, 16), (9, 16)]);
+        assert_eq!(references(at(9, 17), false), vec![(2, 16), (9, 16)]);
+        assert_eq!(references(at(3, 0), true), vec![(3, 0), (6, 16)]);
+        assert_eq!(references(at(7, 0), false), vec![(4, 16)]);
+    }
+
+    #[test]
+    fn finds_operations() {
+        assert_eq!(operation_at(SOURCE, 3, 11), Some(Operation::LDA));
+        assert_eq!(operation_at(SOURCE, 3, 14), Some(Operation::LDA));
+        assert_eq!(operation_at(SOURCE, 3, 16), None);
+        assert_eq!(operation_at(SOURCE, 1, 12), None);
+        assert_eq!(operation_at(SOURCE, 0, 2), None);
+    }
+}
@@ -0,0 +1,398 @@
+//! Relocatable object modules, the input of the linker.
+
+use super::listing::instruction_form;
+use super::{Assembler, Error};
+use crate::{Sign, Word, BYTE};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Module assembled from location 0, which the linker may place anywhere in memory.
+#[derive(Debug, PartialEq, Default)]
+pub struct Object {
+    /// Words by their offsets from the beginning of the module.
+    pub words: BTreeMap<usize, Word>,
+    /// Offsets of the words whose address is an offset within the module.
+    pub relocations: BTreeSet<usize>,
+    /// Offsets of the words whose address is the value of a symbol of another module.
+    pub imports: BTreeMap<usize, String>,
+    /// Symbols that other modules may import, with their offsets.
+    pub exports: BTreeMap<String, usize>,
+    /// Offset of the start location, when END has an address.
+    pub start: Option<usize>,
+    /// Number of locations the module occupies, up to the location counter at END,
+    /// so that space reserved by ORIG at the end belongs to the module.
+    pub size: usize,
+}
+
+impl Object {
+    /// Offset after the last word.
+    fn extent(&self) -> usize {
+        self.words.keys().next_back().map_or(0, |last| last + 1)
+    }
+}
+
+/// Assembles a module for the linker.
+///
+/// `EXTRN A,B` declares symbols defined by other modules, they may only be used as a whole
+/// address part. `ENTRY C,D` exports locations of the module. END may leave out the start.
+///
+/// The module is assembled once from location 0 and once from location 1: words whose
+/// address moves along with the module get relocation entries, other words must not change.
+pub fn assemble_object(source: &str) -> Result<Object, Vec<Error>> {
+    let relocatable = Assembler {
+        relocatable: true,
+        ..Assembler::default()
+    };
+    let (at_zero, image) = relocatable.run(source);
+    if !at_zero.errors.is_empty() {
+        return Err(at_zero.errors);
+    }
+    let (at_one, moved) = Assembler {
+        relocatable: true,
+        base: 1,
+        ..Assembler::default()
+    }
+    .run(source);
+    if !at_one.errors.is_empty() {
+        return Err(at_one.errors);
+    }
+
+    let last_line = source.lines().count();
+    let mut errors = vec![];
+    let mut object = Object {
+        words: image.words.clone(),
+        imports: at_zero.imports.clone(),
+        ..Object::default()
+    };
+    let end = at_zero.pool + at_zero.literals.len() as i64;
+    object.size = object.extent().max(end.max(0) as usize);
+    if object.size > crate::MEMORY_SIZE {
+        errors.push(Error {
+            file: None,
+            line: last_line,
+            message: format!(
+                "module of {} locations does not fit into memory",
+                object.size
+            ),
+        });
+    }
+    // Words after an ORIG that does not move would all differ, the ORIG is the mistake.
+    let mut fixed = None;
+    for (&(line, origin), &(_, moved)) in at_zero.origins.iter().zip(&at_one.origins) {
+        if moved != origin + 1 {
+            fixed = fixed.or(Some(line));
+            errors.push(Error {
+                file: None,
+                line,
+                message: format!(
+                    "ORIG {} cannot be relocated, only locations of the module may be set",
+                    origin
+                ),
+            });
+        }
+    }
+    let line_of = |offset| {
+        at_zero
+            .lines
+            .iter()
+            .find(|(_, (location, _))| *location == offset)
+            .map_or(last_line, |(line, _)| *line)
+    };
+    for (&offset, &word) in &image.words {
+        if fixed.is_some_and(|fixed| line_of(offset) > fixed) {
+            continue;
+        }
+        match moved.words.get(&(offset + 1)) {
+            Some(&other) if other == word => {}
+            Some(&other)
+                if other == with_address(word, address(word) + 1) && address(word) >= 0 =>
+            {
+                object.relocations.insert(offset);
+            }
+            _ => errors.push(Error {
+                file: None,
+                line: line_of(offset),
+                message: format!(
+                    "word at {} cannot be relocated, only addresses (0:2) may refer to locations",
+                    offset
+                ),
+            }),
+        }
+    }
+    for (line, symbol) in &at_zero.entries {
+        match (at_zero.symbols.get(symbol), at_one.symbols.get(symbol)) {
+            (Some(&value), Some(&moved)) if moved == value + 1 => {
+                object.exports.insert(symbol.clone(), value as usize);
+            }
+            (Some(_), Some(_)) => errors.push(Error {
+                file: None,
+                line: *line,
+                message: format!("entry {} is not a location", symbol),
+            }),
+            _ => errors.push(Error {
+                file: None,
+                line: *line,
+                message: format!("undefined symbol {}", symbol),
+            }),
+        }
+    }
+    match (at_zero.start, at_one.start) {
+        (None, None) => {}
+        (Some(start), Some(moved)) if moved == start + 1 => object.start = Some(start as usize),
+        _ => errors.push(Error {
+            file: None,
+            line: last_line,
+            message: "start is not a location".to_string(),
+        }),
+    }
+    if errors.is_empty() {
+        Ok(object)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Signed address in the field (0:2) of the word.
+pub fn address(word: Word) -> i64 {
+    word.slice(crate::Modification::field(0, 2)).value()
+}
+
+/// Word with the address replaced, which must fit into two bytes.
+pub fn with_address(word: Word, address: i64) -> Word {
+    let sign = if address < 0 { Sign::Minus } else { Sign::Plus };
+    let magnitude = address.unsigned_abs() % (BYTE as u64 * BYTE as u64);
+    word.merge(
+        Word::from_magnitude(sign, magnitude),
+        crate::Modification::field(0, 2),
+    )
+}
+
+/// Names of the records of the text form.
+const RECORDS: [&str; 4] = ["SIZE", "START", "ENTRY", "WORD"];
+
+/// Whether the text begins with a record, so that it may be an object rather than MIXAL.
+pub fn is_object(text: &str) -> bool {
+    RECORDS.iter().any(|record| {
+        text.strip_prefix(record)
+            .is_some_and(|rest| rest.starts_with(' '))
+    })
+}
+
+/// Text form with one record per line:
+/// `START offset`, `ENTRY symbol offset` and `WORD offset ± AAAA I F C`,
+/// followed by `R` for relocation or `X symbol` for an import.
+/// `SIZE locations` comes first when the module reserves locations after its last word.
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.size > self.extent() {
+            writeln!(f, "SIZE {:04}", self.size)?;
+        }
+        if let Some(start) = self.start {
+            writeln!(f, "START {:04}", start)?;
+        }
+        for (symbol, offset) in &self.exports {
+            writeln!(f, "ENTRY {} {:04}", symbol, offset)?;
+        }
+        for (&offset, &word) in &self.words {
+            write!(f, "WORD {:04} {}", offset, instruction_form(word))?;
+            if self.relocations.contains(&offset) {
+                write!(f, " R")?;
+            }
+            if let Some(symbol) = self.imports.get(&offset) {
+                write!(f, " X {}", symbol)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Object {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut object = Object::default();
+        for (i, line) in text.lines().enumerate() {
+            let invalid = || format!("line {}: invalid record {:?}", i + 1, line);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |field: &str, limit: usize| {
+                field
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n < limit)
+                    .ok_or_else(invalid)
+            };
+            match fields[..] {
+                [] => {}
+                ["SIZE", size] => object.size = number(size, crate::MEMORY_SIZE + 1)?,
+                ["START", offset] => object.start = Some(number(offset, crate::MEMORY_SIZE)?),
+                ["ENTRY", symbol, offset] => {
+                    let offset = number(offset, crate::MEMORY_SIZE)?;
+                    object.exports.insert(symbol.to_string(), offset);
+                }
+                ["WORD", offset, sign, a, i, f, c, ref linkage @ ..] => {
+                    let offset = number(offset, crate::MEMORY_SIZE)?;
+                    let sign = match sign {
+                        "+" => Sign::Plus,
+                        "-" => Sign::Minus,
+                        _ => return Err(invalid()),
+                    };
+                    let a = number(a, BYTE as usize * BYTE as usize)?;
+                    let byte = |field| number(field, BYTE as usize).map(|b| b as u8);
+                    let word = Word::new(
+                        sign,
+                        (a / BYTE as usize) as u8,
+                        (a % BYTE as usize) as u8,
+                        byte(i)?,
+                        byte(f)?,
+                        byte(c)?,
+                    );
+                    object.words.insert(offset, word);
+                    match linkage {
+                        [] => {}
+                        ["R"] => {
+                            object.relocations.insert(offset);
+                        }
+                        ["X", symbol] => {
+                            object.imports.insert(offset, symbol.to_string());
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        object.size = object.size.max(object.extent());
+        Ok(object)
+    }
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+
+    const MODULE: &str = "           EXTRN PRINT,LIMIT
+           ENTRY START,TABLE
+START      LDA  TABLE,1(1:2)
+           JMP  PRINT
+           ENT1 LIMIT
+           LDX  =START(0:2)=
+           JMP  START
+TABLE      CON  TABLE+1(0:2)
+SIZE       EQU  *-TABLE
+           CON  SIZE
+           HLT  1000
+           END  START";
+
+    #[test]
+    fn marks_relocations_imports_and_exports() {
+        let object = assemble_object(MODULE).unwrap();
+
+        assert_eq!(object.words.len(), 9);
+        assert_eq!(object.words[&0], Word::new(Sign::Plus, 0, 5, 1, 10, 8));
+        assert_eq!(
+            object.relocations.iter().copied().collect::<Vec<_>>(),
+            vec![0, 3, 4, 5, 8]
+        );
+        assert_eq!(
+            object.imports,
+            vec![(1, "PRINT".to_string()), (2, "LIMIT".to_string())]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(
+            object.exports,
+            vec![("START".to_string(), 0), ("TABLE".to_string(), 5)]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(object.start, Some(0));
+        assert_eq!(object.size, 9);
+    }
+
+    #[test]
+    fn text_form_round_trips() {
+        let object = assemble_object(MODULE).unwrap();
+        let text = object.to_string();
+
+        assert_eq!(
+            text.lines().take(6).collect::<Vec<_>>(),
+            vec![
+                "START 0000",
+                "ENTRY START 0000",
+                "ENTRY TABLE 0005",
+                "WORD 0000 + 0005 01 10 08 R",
+                "WORD 0001 + 0000 00 00 39 X PRINT",
+                "WORD 0002 + 0000 00 02 49 X LIMIT",
+            ]
+        );
+        assert_eq!(text.parse::<Object>(), Ok(object));
+        assert!(is_object(&text));
+        assert!(!is_object(""));
+        assert!(!is_object("\n\n"));
+        assert!(!is_object("STARTS     HLT"));
+        assert_eq!(
+            "WORD 0000 + 4096 00 00 00".parse::<Object>(),
+            Err("line 1: invalid record \"WORD 0000 + 4096 00 00 00\"".to_string())
+        );
+        assert_eq!(
+            "\nWORD 0000 + 0001 00 00 00 Y".parse::<Object>(),
+            Err("line 2: invalid record \"WORD 0000 + 0001 00 00 00 Y\"".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_what_cannot_be_relocated() {
+        let errors = |source| {
+            assemble_object(source)
+                .unwrap_err()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            errors(
+                "           ENTRY SIZE,MISSING
+HERE       CON  HERE(4:4)
+           CON  -HERE
+SIZE       EQU  10
+           ORIG 100
+           NOP
+           END  SIZE"
+            ),
+            vec![
+                "line 5: ORIG 100 cannot be relocated, only locations of the module may be set",
+                "line 2: word at 0 cannot be relocated, only addresses (0:2) may refer to locations",
+                "line 3: word at 1 cannot be relocated, only addresses (0:2) may refer to locations",
+                "line 1: entry SIZE is not a location",
+                "line 1: undefined symbol MISSING",
+                "line 7: start is not a location",
+            ]
+        );
+        assert_eq!(
+            errors(
+                "START      ORIG 2000
+           NOP
+           NOP
+           ORIG START+10
+           JMP  START
+           END  START"
+            ),
+            vec!["line 1: ORIG 2000 cannot be relocated, only locations of the module may be set"]
+        );
+        assert_eq!(
+            errors(
+                "           EXTRN OTHER
+           LDA  OTHER+1
+OTHER      NOP
+           END"
+            ),
+            vec![
+                "line 3: external symbol OTHER is defined",
+                "line 2: undefined symbol OTHER",
+            ]
+        );
+    }
+}
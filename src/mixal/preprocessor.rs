@@ -0,0 +1,455 @@
+//! Include files and conditional assembly, a pre-pass that reads the source from files.
+//!
+//! `INCLUDE FILE` inserts the lines of the file, which is found relative to the directory
+//! of the file that includes it. `IF EXPR`, `ELSE` and `ENDIF` keep the lines between them
+//! when the expression is nonzero, it may use symbols defined by EQU before the IF:
+//!
+//! ```text
+//! BYTESIZE   EQU  64
+//!            IF   BYTESIZE-64
+//!            INCLUDE decimal.mixal
+//!            ELSE
+//!            INCLUDE binary.mixal
+//!            ENDIF
+//! ```
+
+use super::expression::{expression, Locals, Scope};
+use super::listing::line_numbers;
+use super::{is_symbol, Error, Statement};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// File and line that a line of the preprocessed source comes from.
+#[derive(Debug, PartialEq)]
+pub struct Origin {
+    pub file: String,
+    pub line: usize,
+}
+
+/// Source without directives, with the origin of every line.
+#[derive(Debug, PartialEq, Default)]
+pub struct Preprocessed {
+    pub source: String,
+    pub origins: Vec<Origin>,
+}
+
+impl Preprocessed {
+    /// Moves errors from the lines of the preprocessed source to their files and lines.
+    pub fn original(&self, errors: Vec<Error>) -> Vec<Error> {
+        errors
+            .into_iter()
+            .map(
+                |error| match error.line.checked_sub(1).and_then(|i| self.origins.get(i)) {
+                    Some(origin) => Error {
+                        file: Some(origin.file.clone()),
+                        line: origin.line,
+                        message: error.message,
+                    },
+                    None => error,
+                },
+            )
+            .collect()
+    }
+
+    /// Names of the lines for listings and cross-references: their numbers when all come
+    /// from one file, otherwise `file:line` padded to the same width.
+    pub fn line_names(&self) -> Vec<String> {
+        if self
+            .origins
+            .windows(2)
+            .all(|pair| pair[0].file == pair[1].file)
+        {
+            return line_numbers(self.origins.iter().map(|origin| origin.line));
+        }
+        let names: Vec<String> = self
+            .origins
+            .iter()
+            .map(|origin| format!("{}:{}", origin.file, origin.line))
+            .collect();
+        let width = names.iter().map(String::len).max().unwrap_or(0);
+        names
+            .iter()
+            .map(|name| format!("{:width$}", name, width = width))
+            .collect()
+    }
+}
+
+/// Resolves includes and conditions, `read` returns the contents of a file.
+pub fn preprocess(
+    path: &Path,
+    read: &mut dyn FnMut(&Path) -> io::Result<String>,
+) -> Result<Preprocessed, Vec<Error>> {
+    let mut preprocessor = Preprocessor {
+        read,
+        symbols: BTreeMap::new(),
+        including: vec![],
+        preprocessed: Preprocessed::default(),
+        errors: vec![],
+    };
+    preprocessor.include(path, None);
+    if preprocessor.errors.is_empty() {
+        Ok(preprocessor.preprocessed)
+    } else {
+        Err(preprocessor.errors)
+    }
+}
+
+/// IF with the lines up to its ELSE or ENDIF.
+struct Condition {
+    line: usize,
+    /// Whether the lines around the IF are kept.
+    outer: bool,
+    value: bool,
+    /// Line of the ELSE, once it is read.
+    otherwise: Option<usize>,
+}
+
+impl Condition {
+    fn keeps(&self) -> bool {
+        self.outer && self.value == self.otherwise.is_none()
+    }
+}
+
+struct Preprocessor<'a> {
+    read: &'a mut dyn FnMut(&Path) -> io::Result<String>,
+    /// Values of the symbols defined by EQU so far, when they do not depend on `*`.
+    symbols: BTreeMap<String, i64>,
+    /// Files that are being read, each one includes the next, with `.` and `..` resolved.
+    including: Vec<PathBuf>,
+    preprocessed: Preprocessed,
+    errors: Vec<Error>,
+}
+
+impl Preprocessor<'_> {
+    /// Reads the file, `from` is the file and line of the INCLUDE.
+    fn include(&mut self, path: &Path, from: Option<(&str, usize)>) {
+        let name = path.display().to_string();
+        let (file, line) = from.unwrap_or((&name, 0));
+        let (file, line) = (file.to_string(), line);
+        let normalized = normalize(path);
+        if self.including.contains(&normalized) {
+            let cycle = self
+                .including
+                .iter()
+                .chain(Some(&normalized))
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            self.error(&file, line, format!("include cycle {}", cycle));
+            return;
+        }
+        let text = match (self.read)(path) {
+            Ok(text) => text,
+            Err(error) => {
+                self.error(&file, line, format!("cannot read {}: {}", name, error));
+                return;
+            }
+        };
+        self.including.push(normalized);
+        let mut conditions: Vec<Condition> = vec![];
+        for (i, text) in text.lines().enumerate() {
+            let line = i + 1;
+            let keeps = conditions.last().is_none_or(Condition::keeps);
+            let statement = match Statement::parse(line, text) {
+                Some(statement) => statement,
+                None => {
+                    if keeps {
+                        self.push(&name, line, text);
+                    }
+                    continue;
+                }
+            };
+            let directive = matches!(
+                statement.operation.as_str(),
+                "IF" | "ELSE" | "ENDIF" | "INCLUDE"
+            );
+            if directive && !statement.location.is_empty() {
+                let message = format!("unexpected label {}", statement.location);
+                self.error(&name, line, message);
+            }
+            match statement.operation.as_str() {
+                "IF" => {
+                    let value = keeps && self.condition(&name, &statement);
+                    conditions.push(Condition {
+                        line,
+                        outer: keeps,
+                        value,
+                        otherwise: None,
+                    });
+                }
+                "ELSE" => match conditions.last_mut() {
+                    Some(condition) if condition.otherwise.is_none() => {
+                        condition.otherwise = Some(line)
+                    }
+                    Some(condition) => {
+                        let message = format!("second ELSE for IF at line {}", condition.line);
+                        self.error(&name, line, message);
+                    }
+                    None => self.error(&name, line, "ELSE without IF".to_string()),
+                },
+                "ENDIF" => {
+                    if conditions.pop().is_none() {
+                        self.error(&name, line, "ENDIF without IF".to_string());
+                    }
+                }
+                _ if !keeps => {}
+                "INCLUDE" if statement.address.is_empty() => {
+                    self.error(&name, line, "missing file name".to_string());
+                }
+                "INCLUDE" => {
+                    let included = path
+                        .parent()
+                        .unwrap_or_else(|| Path::new(""))
+                        .join(&statement.address);
+                    self.include(&included, Some((&name, line)));
+                }
+                "EQU" => {
+                    self.equ(&statement);
+                    self.push(&name, line, text);
+                }
+                _ => self.push(&name, line, text),
+            }
+        }
+        for condition in conditions {
+            self.error(&name, condition.line, "IF without ENDIF".to_string());
+        }
+        self.including.pop();
+    }
+
+    fn condition(&mut self, file: &str, statement: &Statement) -> bool {
+        match self.evaluate(&statement.address, statement.line, 0) {
+            Ok(value) => value != 0,
+            Err(message) => {
+                self.error(file, statement.line, message);
+                false
+            }
+        }
+    }
+
+    /// Records the value of the symbol, unless it depends on the location.
+    fn equ(&mut self, statement: &Statement) {
+        if !is_symbol(&statement.location) {
+            return;
+        }
+        let line = statement.line;
+        let value = self.evaluate(&statement.address, line, 0);
+        if value.is_ok() && value == self.evaluate(&statement.address, line, 1) {
+            self.symbols
+                .insert(statement.location.clone(), value.unwrap_or_default());
+        }
+    }
+
+    fn evaluate(&self, text: &str, line: usize, location: i64) -> Result<i64, String> {
+        let scope = Scope {
+            symbols: &self.symbols,
+            locals: &Locals::default(),
+            line,
+            location,
+        };
+        expression(text, &scope)
+    }
+
+    fn push(&mut self, file: &str, line: usize, text: &str) {
+        self.preprocessed.source.push_str(text);
+        self.preprocessed.source.push('\n');
+        self.preprocessed.origins.push(Origin {
+            file: file.to_string(),
+            line,
+        });
+    }
+
+    fn error(&mut self, file: &str, line: usize, message: String) {
+        self.errors.push(Error {
+            file: Some(file.to_string()),
+            line,
+            message,
+        });
+    }
+}
+
+/// Path with `.` and `..` resolved by its components, so that a file is recognized
+/// however the includes spell it.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod spec {
+    use super::*;
+    use crate::mixal::assemble;
+
+    fn files(files: &[(&str, &str)]) -> impl FnMut(&Path) -> io::Result<String> {
+        let files: BTreeMap<PathBuf, String> = files
+            .iter()
+            .map(|(name, text)| (PathBuf::from(name), text.to_string()))
+            .collect();
+        move |path| {
+            files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found"))
+        }
+    }
+
+    fn errors(read: &mut dyn FnMut(&Path) -> io::Result<String>) -> Vec<String> {
+        preprocess(Path::new("main.mixal"), read)
+            .unwrap_err()
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn includes_files_and_keeps_lines_of_true_conditions() {
+        let mut read = files(&[
+            (
+                "main.mixal",
+                "DEBUG      EQU  1
+BYTESIZE   EQU  64
+HERE       EQU  *
+           IF   DEBUG
+           INCLUDE lib/debug.mixal
+           ENDIF
+           IF   BYTESIZE-64
+           CON  100
+           ELSE
+           CON  64
+           ENDIF
+           END  0",
+            ),
+            ("lib/debug.mixal", "* DEBUG OUTPUT\n           OUT  0(18)"),
+        ]);
+
+        let preprocessed = preprocess(Path::new("main.mixal"), &mut read).unwrap();
+
+        assert_eq!(
+            preprocessed.source,
+            "DEBUG      EQU  1
+BYTESIZE   EQU  64
+HERE       EQU  *
+* DEBUG OUTPUT
+           OUT  0(18)
+           CON  64
+           END  0
+"
+        );
+        assert_eq!(
+            preprocessed
+                .origins
+                .iter()
+                .map(|origin| (origin.file.as_str(), origin.line))
+                .collect::<Vec<_>>(),
+            vec![
+                ("main.mixal", 1),
+                ("main.mixal", 2),
+                ("main.mixal", 3),
+                ("lib/debug.mixal", 1),
+                ("lib/debug.mixal", 2),
+                ("main.mixal", 10),
+                ("main.mixal", 12),
+            ]
+        );
+        assert_eq!(
+            preprocessed.line_names()[2..6].to_vec(),
+            vec![
+                "main.mixal:3     ",
+                "lib/debug.mixal:1",
+                "lib/debug.mixal:2",
+                "main.mixal:10    ",
+            ]
+        );
+    }
+
+    #[test]
+    fn names_lines_of_a_single_file_by_their_numbers() {
+        let mut read = files(&[(
+            "main.mixal",
+            "* ONE\n           IF   0\n* TWO\n           ENDIF\n           END  0",
+        )]);
+
+        let preprocessed = preprocess(Path::new("main.mixal"), &mut read).unwrap();
+
+        assert_eq!(preprocessed.line_names(), vec!["01", "05"]);
+    }
+
+    #[test]
+    fn reports_errors_in_files_where_they_are() {
+        let mut read = files(&[
+            (
+                "main.mixal",
+                "           INCLUDE a.mixal\n           END  0",
+            ),
+            ("a.mixal", "           LDA  MISSING"),
+        ]);
+        let preprocessed = preprocess(Path::new("main.mixal"), &mut read).unwrap();
+
+        let errors = preprocessed.original(assemble(&preprocessed.source).unwrap_err());
+
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec!["a.mixal:1: undefined symbol MISSING"]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_directives() {
+        assert_eq!(
+            errors(&mut files(&[
+                ("main.mixal", "           INCLUDE a.mixal"),
+                ("a.mixal", "           INCLUDE b.mixal"),
+                ("b.mixal", "           INCLUDE a.mixal"),
+            ])),
+            vec!["b.mixal:1: include cycle main.mixal -> a.mixal -> b.mixal -> a.mixal"]
+        );
+        assert_eq!(
+            errors(&mut files(&[
+                ("main.mixal", "           INCLUDE ./lib/a.mixal"),
+                ("./lib/a.mixal", "           INCLUDE ../main.mixal"),
+            ])),
+            vec!["./lib/a.mixal:1: include cycle main.mixal -> lib/a.mixal -> main.mixal"]
+        );
+        assert_eq!(
+            errors(&mut files(&[(
+                "main.mixal",
+                "           INCLUDE missing.mixal
+           ELSE
+           ENDIF
+           IF   UNDEFINED
+           ELSE
+           ELSE
+LABEL      IF   1
+           INCLUDE"
+            )])),
+            vec![
+                "main.mixal:1: cannot read missing.mixal: not found",
+                "main.mixal:2: ELSE without IF",
+                "main.mixal:3: ENDIF without IF",
+                "main.mixal:4: undefined symbol UNDEFINED",
+                "main.mixal:6: second ELSE for IF at line 4",
+                "main.mixal:7: unexpected label LABEL",
+                "main.mixal:8: missing file name",
+                "main.mixal:4: IF without ENDIF",
+                "main.mixal:7: IF without ENDIF",
+            ]
+        );
+        assert_eq!(
+            errors(&mut files(&[])),
+            vec!["main.mixal:0: cannot read main.mixal: not found"]
+        );
+    }
+}